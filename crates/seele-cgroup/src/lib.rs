@@ -67,6 +67,23 @@ pub fn initialize_cgroup_subtrees() -> Result<()> {
     Ok(())
 }
 
+/// Create a cgroup in the container slice to hold the cgroup of a container
+/// created by runj, whose statistics include the ones of the container
+pub fn create_container_parent_cgroup(name: &str) -> Result<PathBuf> {
+    let path = CGROUP_CONTAINER_SLICE_PATH.join(name);
+    fs::create_dir(&path)
+        .with_context(|| format!("Error creating the cgroup: {}", path.display()))?;
+
+    if let Err(err) =
+        write_cgroup_file_str(path.join("cgroup.subtree_control"), MANDATORY_CONTROLLERS)
+    {
+        _ = fs::remove_dir(&path);
+        return Err(err.into());
+    }
+
+    Ok(path)
+}
+
 pub fn bind_application_threads() -> Result<()> {
     let available_cpus = {
         let mut cpus: Vec<u32> = vec![];
@@ -0,0 +1,125 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use serde::Deserialize;
+
+use crate::{ComposerQueueItem, entities::SubmissionPriority};
+
+/// The received submissions waiting for a slot, which are admitted by their
+/// priorities, and then in their order of arrival
+#[derive(Default)]
+pub struct AdmissionQueue {
+    next_sequence: u64,
+    items: BinaryHeap<QueuedSubmission>,
+}
+
+struct QueuedSubmission {
+    priority: SubmissionPriority,
+    sequence: Reverse<u64>,
+    item: ComposerQueueItem,
+}
+
+impl PartialEq for QueuedSubmission {
+    fn eq(&self, other: &Self) -> bool {
+        (self.priority, self.sequence) == (other.priority, other.sequence)
+    }
+}
+
+impl Eq for QueuedSubmission {}
+
+impl PartialOrd for QueuedSubmission {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedSubmission {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.priority, self.sequence).cmp(&(other.priority, other.sequence))
+    }
+}
+
+impl AdmissionQueue {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, item: ComposerQueueItem) {
+        let sequence = Reverse(self.next_sequence);
+        self.next_sequence += 1;
+        self.items.push(QueuedSubmission { priority: peek_priority(&item), sequence, item });
+    }
+
+    pub fn pop(&mut self) -> Option<ComposerQueueItem> {
+        self.items.pop().map(|queued| queued.item)
+    }
+}
+
+/// Read the priority of the submission before it is handled, which overrides
+/// the one provided by the exchange. The malformed submissions are left to fail
+/// when being handled.
+fn peek_priority(item: &ComposerQueueItem) -> SubmissionPriority {
+    #[derive(Deserialize)]
+    struct Header {
+        #[serde(default)]
+        priority: Option<SubmissionPriority>,
+    }
+
+    item.format
+        .deserialize::<Header>(&item.config)
+        .ok()
+        .and_then(|header| header.priority)
+        .or(item.priority)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use ring_channel::ring_channel;
+
+    use super::AdmissionQueue;
+    use crate::{ComposerQueueItem, DataFormat, entities::SubmissionPriority};
+
+    fn make_item(config: &str, priority: Option<SubmissionPriority>) -> ComposerQueueItem {
+        let (status_tx, _) = ring_channel(NonZeroUsize::MIN);
+        ComposerQueueItem {
+            config: config.as_bytes().to_vec(),
+            format: DataFormat::Yaml,
+            priority,
+            attachments: Default::default(),
+            status_tx,
+        }
+    }
+
+    #[test]
+    fn test_admission_queue() {
+        let mut queue = AdmissionQueue::default();
+        queue.push(make_item("id: low\npriority: low", None));
+        queue.push(make_item("id: normal-1", None));
+        queue.push(make_item("id: high-1", Some(SubmissionPriority::High)));
+        queue.push(make_item("id: normal-2\npriority: normal", Some(SubmissionPriority::High)));
+        queue.push(make_item("id: high-2\npriority: high", None));
+        queue.push(make_item("{", Some(SubmissionPriority::Low)));
+
+        let mut order = vec![];
+        while let Some(item) = queue.pop() {
+            order.push(String::from_utf8(item.config).unwrap());
+        }
+        assert_eq!(
+            order,
+            [
+                "id: high-1",
+                "id: high-2\npriority: high",
+                "id: normal-1",
+                "id: normal-2\npriority: normal",
+                "id: low\npriority: low",
+                "{"
+            ]
+        );
+    }
+}
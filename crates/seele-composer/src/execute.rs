@@ -1,4 +1,4 @@
-use std::{iter, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, iter, path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Result, bail};
 use async_recursion::async_recursion;
@@ -13,12 +13,16 @@ use tracing::{Span, debug, instrument};
 
 use super::predicate;
 use crate::{
+    journal::{Journal, JournalTaskStatus},
     report::apply_embeds_config,
-    shared::entities::{
-        ActionTaskConfig, ParallelFailedReport, ParallelSuccessReport, ParallelTaskConfig,
-        SequenceFailedReport, SequenceSuccessReport, Submission, SubmissionReportUploadConfig,
-        TaskConfig, TaskConfigExt, TaskEmbeds, TaskFailedReport, TaskNode, TaskNodeExt,
-        TaskReportWhenConfig, TaskStatus, TaskSuccessReport,
+    shared::{
+        entities::{
+            ActionTaskConfig, ParallelFailedReport, ParallelSuccessReport, ParallelTaskConfig,
+            SequenceFailedReport, SequenceSuccessReport, Submission, SubmissionReportUploadConfig,
+            TaskConfig, TaskConfigExt, TaskEmbeds, TaskFailedReport, TaskNode, TaskNodeExt,
+            TaskReportWhenConfig, TaskStatus, TaskSuccessReport,
+        },
+        runner::Scheduling,
     },
     worker::{WorkerQueueItem, WorkerQueueTx},
 };
@@ -38,6 +42,9 @@ macro_rules! join_errors {
 struct ExecutionContext {
     submission_id: String,
     submission_root: PathBuf,
+    scheduling: Scheduling,
+    attachments: Arc<HashMap<String, PathBuf>>,
+    journal: Arc<Journal>,
     worker_queue_tx: WorkerQueueTx,
     progress_tx: Mutex<RingSender<()>>,
     upload_configs: Mutex<Vec<SubmissionReportUploadConfig>>,
@@ -46,12 +53,18 @@ struct ExecutionContext {
 #[instrument(skip_all)]
 pub async fn execute_submission(
     submission: Arc<Submission>,
+    scheduling: Scheduling,
+    attachments: Arc<HashMap<String, PathBuf>>,
+    journal: Arc<Journal>,
     worker_queue_tx: WorkerQueueTx,
     progress_tx: RingSender<()>,
 ) -> Result<Vec<SubmissionReportUploadConfig>> {
     let ctx = ExecutionContext {
         submission_id: submission.id.clone(),
         submission_root: submission.root_directory.clone(),
+        scheduling,
+        attachments,
+        journal,
         worker_queue_tx,
         progress_tx: Mutex::new(progress_tx),
         upload_configs: Mutex::default(),
@@ -75,6 +88,7 @@ async fn track_task_execution(ctx: &ExecutionContext, node: Arc<TaskNode>) -> Re
     {
         *node.config.status.write().unwrap() = TaskStatus::Running;
     }
+    ctx.journal.record_task(&node.name, JournalTaskStatus::Running).await;
 
     let status = match &node.ext {
         TaskNodeExt::Action(config) => {
@@ -116,6 +130,9 @@ async fn track_task_execution(ctx: &ExecutionContext, node: Arc<TaskNode>) -> Re
         );
     }
 
+    if let Some(status) = JournalTaskStatus::from_status(&status) {
+        ctx.journal.record_task(&node.name, status).await;
+    }
     {
         *node.config.status.write().unwrap() = status;
     }
@@ -158,6 +175,9 @@ async fn track_action_execution(
             parent_span: Span::current(),
             submission_root: ctx.submission_root.clone(),
             submission_id: ctx.submission_id.clone(),
+            scheduling: ctx.scheduling.clone(),
+            tags: node.config.tags.clone().unwrap_or_default(),
+            attachments: ctx.attachments.clone(),
             config,
             report_tx: tx,
         })
@@ -314,7 +334,16 @@ mod tests {
                 let (progress_tx, _progress_rx) =
                     ring_channel::ring_channel(NonZeroUsize::new(1).unwrap());
                 let handle = tokio::spawn(async move {
-                    super::execute_submission(submission, worker_tx, progress_tx).await.unwrap();
+                    super::execute_submission(
+                        submission,
+                        Default::default(),
+                        Default::default(),
+                        Default::default(),
+                        worker_tx,
+                        progress_tx,
+                    )
+                    .await
+                    .unwrap();
                 });
 
                 let mut results = vec![];
@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// The encoding of the submissions received and the signals sent by the
+/// exchanges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataFormat {
+    #[default]
+    Yaml,
+    Json,
+    MessagePack,
+}
+
+impl DataFormat {
+    /// Parse the media type like `application/json; charset=utf-8`
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(Self::Yaml)
+            }
+            "application/json" | "text/json" => Some(Self::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MessagePack)
+            }
+            _ => None,
+        }
+    }
+
+    /// Pick the first supported format from an `Accept` header
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(Self::from_mime)
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Yaml => "application/yaml",
+            Self::Json => "application/json",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, data: &[u8]) -> Result<T> {
+        match self {
+            Self::Yaml => serde_yaml::from_slice(data).context("Error parsing the yaml"),
+            Self::Json => serde_json::from_slice(data).context("Error parsing the json"),
+            Self::MessagePack => {
+                rmp_serde::from_slice(data).context("Error parsing the message pack")
+            }
+        }
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Yaml => serde_yaml::to_string(value)
+                .map(String::into_bytes)
+                .context("Error serializing the yaml"),
+            Self::Json => serde_json::to_vec(value).context("Error serializing the json"),
+            Self::MessagePack => {
+                rmp_serde::to_vec_named(value).context("Error serializing the message pack")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use insta::glob;
+
+    use super::DataFormat;
+    use crate::entities::SubmissionConfig;
+
+    #[test]
+    fn test_from_mime() {
+        assert_eq!(
+            DataFormat::from_mime("application/json; charset=utf-8"),
+            Some(DataFormat::Json)
+        );
+        assert_eq!(DataFormat::from_mime("Application/X-YAML"), Some(DataFormat::Yaml));
+        assert_eq!(DataFormat::from_mime("text/plain"), None);
+        assert_eq!(
+            DataFormat::from_accept("text/html, application/msgpack;q=0.9, */*"),
+            Some(DataFormat::MessagePack)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_submission() {
+        glob!("tests/*.yaml", |path| {
+            let yaml = fs::read(path).unwrap();
+            let value = DataFormat::Yaml.deserialize::<serde_yaml::Value>(&yaml).unwrap();
+            let parse = |format: DataFormat, data: Vec<u8>| {
+                let config = format.deserialize::<SubmissionConfig>(&data).unwrap();
+                serde_json::to_value(config).unwrap()
+            };
+
+            let expected = parse(DataFormat::Yaml, yaml);
+            let json = DataFormat::Json.serialize(&value).unwrap();
+            let message_pack = DataFormat::MessagePack.serialize(&value).unwrap();
+            assert_eq!(parse(DataFormat::Json, json), expected);
+            assert_eq!(parse(DataFormat::MessagePack, message_pack), expected);
+        });
+    }
+}
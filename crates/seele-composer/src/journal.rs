@@ -0,0 +1,364 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    SubmissionErrorSignal, SubmissionSignal, SubmissionSignalExt,
+    conf::{self, RecoveryPolicy},
+    entities::TaskStatus,
+    registry::{self, FailedSubmission},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    at: DateTime<Utc>,
+
+    #[serde(flatten)]
+    event: JournalEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEvent {
+    Started {
+        id: String,
+    },
+    Task {
+        name: String,
+        status: JournalTaskStatus,
+    },
+
+    /// Appended by the recovery under the `fail` policy, after which the
+    /// journal is kept until the submission is received again or expired
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum JournalTaskStatus {
+    Running,
+    Failed,
+    Success,
+}
+
+impl JournalTaskStatus {
+    pub fn from_status(status: &TaskStatus) -> Option<Self> {
+        match status {
+            TaskStatus::Running => Some(Self::Running),
+            TaskStatus::Failed { .. } => Some(Self::Failed),
+            TaskStatus::Success { .. } => Some(Self::Success),
+            TaskStatus::Pending | TaskStatus::Skipped => None,
+        }
+    }
+}
+
+/// Records the status transitions of a submission into a file in the journal
+/// directory, which is removed once the submission is handled. The entries are
+/// written without syncing, which survive the crashes of the process but not
+/// the ones of the machine.
+///
+/// The default journal records nothing.
+#[derive(Debug, Default)]
+pub struct Journal {
+    file: Option<Mutex<(PathBuf, File)>>,
+}
+
+impl Journal {
+    /// Create the journal of the submission, which records nothing if failed,
+    /// since the submission can still be handled without being recoverable
+    pub async fn create(directory: &Path, id: &str) -> Self {
+        let path = directory.join(format!("{}.jsonl", nano_id::base62::<16>()));
+        let result = async {
+            let mut file = File::create(&path).await.context("Error creating the file")?;
+            let entry =
+                JournalEntry { at: Utc::now(), event: JournalEvent::Started { id: id.to_owned() } };
+            write_entry(&mut file, &entry).await?;
+            file.sync_data().await.context("Error syncing the file")?;
+            anyhow::Ok(file)
+        }
+        .await;
+
+        match result {
+            Ok(file) => Self { file: Some(Mutex::new((path, file))) },
+            Err(err) => {
+                error!(path = %path.display(), "Error creating the journal: {err:#}");
+                _ = fs::remove_file(&path).await;
+                Self::default()
+            }
+        }
+    }
+
+    pub async fn record_task(&self, name: &str, status: JournalTaskStatus) {
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        let entry = JournalEntry {
+            at: Utc::now(),
+            event: JournalEvent::Task { name: name.to_owned(), status },
+        };
+        if let Err(err) = write_entry(&mut file.lock().await.1, &entry).await {
+            error!("Error writing the journal: {err:#}");
+        }
+    }
+
+    /// Remove the journal after the submission is handled
+    pub async fn remove(self) {
+        if let Some(file) = self.file {
+            let (path, file) = file.into_inner();
+            drop(file);
+            if let Err(err) = fs::remove_file(&path).await {
+                error!(path = %path.display(), "Error removing the journal: {err:#}");
+            }
+        }
+    }
+}
+
+async fn write_entry(file: &mut File, entry: &JournalEntry) -> Result<()> {
+    let mut data = serde_json::to_vec(entry).context("Error serializing the entry")?;
+    data.push(b'\n');
+    file.write_all(&data).await.context("Error writing the entry")?;
+    file.flush().await.context("Error flushing the entry")
+}
+
+/// A submission whose journal is left by the last run
+#[derive(Debug)]
+struct InterruptedSubmission {
+    id: String,
+    started_at: DateTime<Utc>,
+    interrupted_at: DateTime<Utc>,
+    failed_at: Option<DateTime<Utc>>,
+
+    /// Tasks which were still running, in the order they started
+    running_tasks: Vec<String>,
+}
+
+impl InterruptedSubmission {
+    fn error_signal(&self) -> SubmissionSignal {
+        let mut error = format!(
+            "The submission was interrupted by a restart of seele, it started at {} and the last \
+             recorded transition was at {}",
+            self.started_at.to_rfc3339(),
+            self.interrupted_at.to_rfc3339()
+        );
+        if !self.running_tasks.is_empty() {
+            error.push_str(&format!(", running tasks: {}", self.running_tasks.join(", ")));
+        }
+
+        SubmissionSignal {
+            id: Some(self.id.clone()),
+            ext: SubmissionSignalExt::Error(SubmissionErrorSignal { error }),
+        }
+    }
+}
+
+/// Replay the journal, ignoring the last entry if it was partially written
+fn read_journal(data: &[u8]) -> Option<InterruptedSubmission> {
+    let mut entries = data
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map_while(|line| serde_json::from_slice::<JournalEntry>(line).ok());
+
+    let first = entries.next()?;
+    let JournalEvent::Started { id } = first.event else {
+        return None;
+    };
+
+    let mut submission = InterruptedSubmission {
+        id,
+        started_at: first.at,
+        interrupted_at: first.at,
+        failed_at: None,
+        running_tasks: vec![],
+    };
+    let mut tasks = HashMap::new();
+    for (i, entry) in entries.enumerate() {
+        match entry.event {
+            JournalEvent::Started { .. } => {}
+            JournalEvent::Task { name, status } => {
+                submission.interrupted_at = entry.at;
+                tasks.insert(name, (i, status));
+            }
+            JournalEvent::Failed => submission.failed_at = Some(entry.at),
+        }
+    }
+
+    let mut running_tasks = tasks
+        .into_iter()
+        .filter(|(_, (_, status))| *status == JournalTaskStatus::Running)
+        .map(|(name, (i, _))| (i, name))
+        .collect::<Vec<_>>();
+    running_tasks.sort();
+    submission.running_tasks = running_tasks.into_iter().map(|(_, name)| name).collect();
+    Some(submission)
+}
+
+/// Clean up the submissions interrupted by the last run according to their
+/// journals, and fail them when they are received again unless the recovery
+/// policy is `retry`. The unreadable journals are moved aside with the
+/// `.unreadable` extension, so that they do not stop seele from starting.
+pub async fn recover_submissions() -> Result<()> {
+    let ttl = TimeDelta::seconds(conf::CONFIG.composer.recovery_ttl_secs as i64);
+    let mut entries =
+        fs::read_dir(&conf::PATHS.journal).await.context("Error reading the journal directory")?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "jsonl") {
+            continue;
+        }
+
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(err) => {
+                error!(path = %path.display(), "Error reading the journal, moving it aside: {err:#}");
+                if let Err(err) = fs::rename(&path, path.with_extension("unreadable")).await {
+                    error!(path = %path.display(), "Error moving the journal: {err:#}");
+                }
+                continue;
+            }
+        };
+
+        let keep = match read_journal(&data) {
+            None => {
+                warn!(path = %path.display(), "Removing the malformed journal");
+                false
+            }
+            // Failed by an earlier run and not received since
+            Some(submission @ InterruptedSubmission { failed_at: Some(failed_at), .. }) => {
+                let expires_at = failed_at + ttl;
+                if expires_at > Utc::now() {
+                    let signal = submission.error_signal();
+                    registry::insert_failed(
+                        &submission.id,
+                        FailedSubmission { signal, journal: path.clone(), expires_at },
+                    );
+                    true
+                } else {
+                    info!(path = %path.display(), "Removing the expired journal");
+                    false
+                }
+            }
+            Some(submission) => {
+                info!(
+                    seele.submission.id = %submission.id,
+                    "Recovering the interrupted submission, running tasks: {:?}",
+                    submission.running_tasks
+                );
+
+                let submission_root = conf::PATHS.submissions.join(&submission.id);
+                if fs::metadata(&submission_root).await.is_ok() {
+                    fs::remove_dir_all(&submission_root).await.with_context(|| {
+                        format!(
+                            "Error removing the submission directory: {}",
+                            submission_root.display()
+                        )
+                    })?;
+                }
+
+                if conf::CONFIG.composer.recovery_policy == RecoveryPolicy::Fail {
+                    let failed_at = Utc::now();
+                    if let Err(err) = mark_failed(&path, &data, failed_at).await {
+                        // Still failed by this run, but not by the next one
+                        error!(path = %path.display(), "Error marking the journal: {err:#}");
+                    }
+
+                    let signal = submission.error_signal();
+                    registry::insert_failed(
+                        &submission.id,
+                        FailedSubmission {
+                            signal,
+                            journal: path.clone(),
+                            expires_at: failed_at + ttl,
+                        },
+                    );
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+
+        if !keep {
+            fs::remove_file(&path)
+                .await
+                .with_context(|| format!("Error removing the journal: {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Append the `Failed` entry after the last complete entry of the journal data
+async fn mark_failed(path: &Path, data: &[u8], at: DateTime<Utc>) -> Result<()> {
+    let mut file =
+        OpenOptions::new().append(true).open(path).await.context("Error opening the file")?;
+    let len = data.iter().rposition(|byte| *byte == b'\n').map_or(0, |i| i + 1);
+    file.set_len(len as u64).await.context("Error truncating the file")?;
+    write_entry(&mut file, &JournalEntry { at, event: JournalEvent::Failed }).await?;
+    file.sync_data().await.context("Error syncing the file")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{Journal, JournalTaskStatus, mark_failed, read_journal};
+
+    #[tokio::test]
+    async fn test_read_journal() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let journal = Journal::create(directory.path(), "foo").await;
+        journal.record_task("prepare", JournalTaskStatus::Running).await;
+        journal.record_task("compile", JournalTaskStatus::Running).await;
+        journal.record_task("prepare", JournalTaskStatus::Success).await;
+        journal.record_task("run.0", JournalTaskStatus::Running).await;
+
+        let path = std::fs::read_dir(directory.path()).unwrap().next().unwrap().unwrap().path();
+        let mut data = std::fs::read(&path).unwrap();
+        // The entry partially written by the crash is ignored
+        data.extend_from_slice(br#"{"at":"#);
+
+        let submission = read_journal(&data).unwrap();
+        assert_eq!(submission.id, "foo");
+        assert_eq!(submission.running_tasks, ["compile", "run.0"]);
+        assert!(submission.failed_at.is_none());
+
+        journal.remove().await;
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_mark_failed() {
+        let directory = tempfile::tempdir().unwrap();
+
+        let journal = Journal::create(directory.path(), "foo").await;
+        journal.record_task("compile", JournalTaskStatus::Running).await;
+        drop(journal);
+
+        let path = std::fs::read_dir(directory.path()).unwrap().next().unwrap().unwrap().path();
+        let mut data = std::fs::read(&path).unwrap();
+        data.extend_from_slice(br#"{"at":"#);
+        std::fs::write(&path, &data).unwrap();
+
+        // The partial entry is dropped, so the marker is not appended to it
+        let failed_at = Utc::now();
+        mark_failed(&path, &data, failed_at).await.unwrap();
+
+        let submission = read_journal(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(submission.failed_at, Some(failed_at));
+        assert_eq!(submission.running_tasks, ["compile"]);
+        assert!(submission.interrupted_at < failed_at);
+    }
+}
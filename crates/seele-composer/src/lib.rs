@@ -1,4 +1,4 @@
-use std::{num::NonZeroUsize, sync::Arc};
+use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Result, bail};
 use chrono::Utc;
@@ -10,29 +10,51 @@ use seele_config as conf;
 use seele_shared as shared;
 use seele_shared::entities;
 use seele_worker as worker;
+use serde::Serialize;
 use tokio::{
     fs,
-    sync::mpsc::{self, error::TryRecvError},
+    sync::{
+        Semaphore,
+        mpsc::{self, error::TryRecvError},
+    },
     time::Instant,
 };
-use tokio_graceful_shutdown::{FutureExt, SubsystemHandle};
-use tracing::{Span, debug, error, field, instrument};
-
-pub use self::signal::*;
+use tokio_graceful_shutdown::SubsystemHandle;
+use tracing::{Span, debug, error, field, info, instrument};
+
+pub use self::{
+    format::*,
+    journal::recover_submissions,
+    retention::{
+        RetainedSubmission, find_retained_submission, list_retained_submissions,
+        remove_retained_submission,
+    },
+    signal::*,
+    validate::ValidationError,
+};
 use crate::{
-    entities::{Submission, SubmissionConfig},
+    admission::AdmissionQueue,
+    entities::{RootTaskNode, Submission, SubmissionConfig, SubmissionPriority},
+    journal::Journal,
+    registry::Registration,
     report::apply_uploads_config,
     reporter::execute_reporter,
-    shared::metrics,
+    shared::{metrics, runner::Scheduling},
     worker::WorkerQueueTx,
 };
 
+mod admission;
 mod execute;
+mod format;
+mod journal;
 mod predicate;
+mod registry;
 mod report;
 mod reporter;
 mod resolve;
+mod retention;
 mod signal;
+mod validate;
 
 pub type ComposerQueueTx = mpsc::Sender<ComposerQueueItem>;
 pub type ComposerQueueRx = mpsc::Receiver<ComposerQueueItem>;
@@ -43,7 +65,17 @@ const SUBMISSION_ATTRIBUTE: &str = "seele.submission.attribute";
 
 #[derive(Debug)]
 pub struct ComposerQueueItem {
-    pub config_yaml: String,
+    pub config: Vec<u8>,
+    pub format: DataFormat,
+
+    /// The priority provided by the exchange, which is overridden by the one in
+    /// the submission
+    pub priority: Option<SubmissionPriority>,
+
+    /// Files uploaded along with the submission, keyed by their names. They are
+    /// removed after the submission is handled.
+    pub attachments: HashMap<String, PathBuf>,
+
     pub status_tx: ring_channel::RingSender<SubmissionSignal>,
 }
 
@@ -52,8 +84,51 @@ pub async fn composer_main(
     mut composer_queue_rx: ComposerQueueRx,
     worker_queue_tx: WorkerQueueTx,
 ) -> Result<()> {
-    while let Ok(Some(item)) = composer_queue_rx.recv().cancel_on_shutdown(&handle).await {
-        tokio::spawn(handle_submission(worker_queue_tx.clone(), item.config_yaml, item.status_tx));
+    registry::init();
+    tokio::spawn(retention::prune_periodically());
+
+    // The submissions wait in the admission queue until a slot is released, and
+    // are left in the channel once the queue is full, so that the exchanges
+    // notice it and stop accepting new ones
+    let slots = Arc::new(Semaphore::new(conf::CONFIG.composer.max_in_flight_submissions.max(1)));
+    let max_queued = conf::CONFIG.composer.max_queued_submissions.max(1);
+    let mut queue = AdmissionQueue::default();
+    loop {
+        let permit = tokio::select! {
+            biased;
+            _ = handle.on_shutdown_requested() => break,
+            // The arrived submissions are queued first to compete for the slot
+            item = composer_queue_rx.recv(), if queue.len() < max_queued => match item {
+                None => break,
+                Some(item) => {
+                    queue.push(item);
+                    continue;
+                }
+            },
+            permit = slots.clone().acquire_owned(), if !queue.is_empty() => match permit {
+                Err(_) => break,
+                Ok(permit) => permit,
+            },
+        };
+        let item = queue.pop().unwrap();
+
+        tokio::spawn({
+            let worker_queue_tx = worker_queue_tx.clone();
+            async move {
+                let _permit = permit;
+                let attachments = Arc::new(item.attachments);
+                handle_submission(
+                    worker_queue_tx,
+                    item.config,
+                    item.format,
+                    item.priority,
+                    attachments.clone(),
+                    item.status_tx,
+                )
+                .await;
+                remove_attachments(&attachments).await;
+            }
+        });
     }
 
     Ok(())
@@ -62,17 +137,21 @@ pub async fn composer_main(
 #[instrument(skip_all, fields(seele.submission.id = field::Empty, seele.submission.attribute = field::Empty, seele.submission.status = field::Empty))]
 async fn handle_submission(
     worker_queue_tx: WorkerQueueTx,
-    config_yaml: String,
+    config: Vec<u8>,
+    format: DataFormat,
+    priority: Option<SubmissionPriority>,
+    attachments: Arc<HashMap<String, PathBuf>>,
     status_tx: RingSender<SubmissionSignal>,
 ) {
     let begin = Instant::now();
 
-    let submission = serde_yaml::from_str::<Arc<SubmissionConfig>>(&config_yaml);
+    let submission = parse_submission(&config, format);
     let Ok(submission) = submission else {
+        let errors = submission.err().unwrap().iter().map(ToString::to_string).collect::<Vec<_>>();
         let message = format!(
-            "Error parsing the submission: {:#}, partial content: {}",
-            submission.err().unwrap(),
-            config_yaml.as_str().truncate_ellipse(256)
+            "Error parsing the submission: {}, partial content: {}",
+            errors.join("; "),
+            String::from_utf8_lossy(&config).as_ref().truncate_ellipse(256)
         );
         error!(message);
 
@@ -85,7 +164,25 @@ async fn handle_submission(
 
     Span::current().record(SUBMISSION_ID, &submission.id);
     Span::current().record(SUBMISSION_ATTRIBUTE, &submission.tracing_attribute);
-    let signal_type = do_handle_submission(submission, worker_queue_tx, status_tx).await;
+
+    let status_tx = match registry::register(&submission.id, status_tx) {
+        Registration::Started(status_tx) => status_tx,
+        Registration::Attached => {
+            info!("Attached to the running submission with the same id");
+            return;
+        }
+        Registration::Cached => {
+            info!("Responded with the result of the completed submission with the same id");
+            return;
+        }
+    };
+
+    let scheduling = Scheduling {
+        priority: submission.priority.or(priority).unwrap_or_default(),
+        tenant: submission.tenant.as_deref().unwrap_or(conf::DEFAULT_TENANT).into(),
+    };
+    let signal_type =
+        do_handle_submission(submission, scheduling, attachments, worker_queue_tx, status_tx).await;
     Span::current().record(SUBMISSION_STATUS, signal_type);
 
     let duration = {
@@ -99,14 +196,58 @@ async fn handle_submission(
     );
 }
 
+/// Parse the submission, explaining the failure with the path-qualified errors
+/// from the validation
+fn parse_submission(
+    config: &[u8],
+    format: DataFormat,
+) -> Result<Arc<SubmissionConfig>, Vec<ValidationError>> {
+    format.deserialize(config).map_err(|err| {
+        let errors = validate::validate_submission(format, config);
+        if errors.is_empty() { vec![ValidationError::new("", format!("{err:#}"))] } else { errors }
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DryRunReport {
+    Resolved { id: String, root_node: Arc<RootTaskNode> },
+    Invalid { errors: Vec<ValidationError> },
+}
+
+/// Parse, validate and resolve the submission without executing it
+pub fn dry_run_submission(config: &[u8], format: DataFormat) -> DryRunReport {
+    let submission = match parse_submission(config, format) {
+        Err(errors) => return DryRunReport::Invalid { errors },
+        Ok(submission) => submission,
+    };
+
+    let submission_root = conf::PATHS.submissions.join(&submission.id);
+    match resolve::resolve_submission(submission, submission_root) {
+        Err(err) => DryRunReport::Invalid {
+            errors: vec![ValidationError::new(
+                "",
+                format!("Failed to resolve the submission: {err:#}"),
+            )],
+        },
+        Ok(submission) => {
+            DryRunReport::Resolved { id: submission.id, root_node: submission.root_node }
+        }
+    }
+}
+
 async fn do_handle_submission(
     submission: Arc<SubmissionConfig>,
+    scheduling: Scheduling,
+    attachments: Arc<HashMap<String, PathBuf>>,
     worker_queue_tx: WorkerQueueTx,
     status_tx: RingSender<SubmissionSignal>,
 ) -> &'static str {
     let submission_root = conf::PATHS.submissions.join(&submission.id);
+    let journal = Arc::new(Journal::create(&conf::PATHS.journal, &submission.id).await);
 
     let inner_submission = submission.clone();
+    let inner_journal = journal.clone();
     let inner_status_tx = status_tx.clone();
     let result = async {
         if fs::metadata(&submission_root).await.is_ok() {
@@ -136,9 +277,16 @@ async fn do_handle_submission(
         });
 
         debug!("Executing the submission");
-        let uploads = execute::execute_submission(submission.clone(), worker_queue_tx, progress_tx)
-            .await
-            .context("Error executing the submission")?;
+        let uploads = execute::execute_submission(
+            submission.clone(),
+            scheduling,
+            attachments,
+            inner_journal,
+            worker_queue_tx,
+            progress_tx,
+        )
+        .await
+        .context("Error executing the submission")?;
 
         let status = serde_json::to_value(&submission.config)
             .context("Error serializing the submission report")?;
@@ -183,6 +331,7 @@ async fn do_handle_submission(
         }
     };
     let signal_type = ext.get_type();
+    let failed = matches!(ext, SubmissionSignalExt::Error(_));
 
     debug!("Sending the final submission signal");
     _ = status_tx.send(SubmissionSignal { id: Some(submission.id.clone()), ext });
@@ -194,7 +343,10 @@ async fn do_handle_submission(
         }
     }
 
-    _ = fs::remove_dir_all(submission_root).await;
+    retention::retain_or_remove(&submission.id, &submission_root, failed).await;
+    if let Some(journal) = Arc::into_inner(journal) {
+        journal.remove().await;
+    }
 
     signal_type
 }
@@ -260,3 +412,11 @@ async fn handle_progress_report(
         }
     }
 }
+
+async fn remove_attachments(attachments: &HashMap<String, PathBuf>) {
+    for path in attachments.values() {
+        if let Err(err) = fs::remove_file(path).await {
+            error!(path = %path.display(), "Error removing the attachment: {err:#}");
+        }
+    }
+}
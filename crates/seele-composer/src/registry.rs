@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    fs,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use moka::sync::Cache;
+use ring_channel::{RingReceiver, RingSender, ring_channel};
+use tracing::error;
+
+use crate::{
+    SubmissionSignal, SubmissionSignalExt, conf,
+    shared::cache::{self, CacheCounter, CacheStats, ManagedCache},
+};
+
+const CACHE_NAME: &str = "seele-submission-results";
+
+static REGISTRY: LazyLock<Arc<SubmissionRegistry>> = LazyLock::new(|| {
+    let config = &conf::CONFIG.composer;
+    Arc::new(SubmissionRegistry::new(
+        Duration::from_secs(config.result_ttl_secs),
+        config.max_cached_results,
+    ))
+});
+
+pub fn init() {
+    cache::register(REGISTRY.clone());
+}
+
+pub fn register(id: &str, status_tx: RingSender<SubmissionSignal>) -> Registration {
+    REGISTRY.register(id, status_tx)
+}
+
+/// Keep the final signal of the submission interrupted by the last run, whose
+/// journal is removed once the signal is sent or expired
+pub fn insert_failed(id: &str, failed: FailedSubmission) {
+    REGISTRY.failed.lock().unwrap().insert(id.to_owned(), failed);
+}
+
+pub enum Registration {
+    /// The submission should be executed, sending its signals to the sender
+    Started(RingSender<SubmissionSignal>),
+
+    /// The submission with the same id is being executed, whose signals will
+    /// also be sent to the subscriber
+    Attached,
+
+    /// The submission with the same id has completed recently or was
+    /// interrupted by the last run, whose final signal has been sent to the
+    /// subscriber
+    Cached,
+}
+
+pub struct FailedSubmission {
+    pub signal: SubmissionSignal,
+    pub journal: PathBuf,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FailedSubmission {
+    fn remove_journal(&self) {
+        if let Err(err) = fs::remove_file(&self.journal) {
+            error!(path = %self.journal.display(), "Error removing the journal: {err:#}");
+        }
+    }
+}
+
+struct InFlight {
+    subscribers: Vec<RingSender<SubmissionSignal>>,
+    last_signal: Option<SubmissionSignal>,
+}
+
+/// Tracks the submissions being executed and the recently completed ones by
+/// their ids, so that the duplicate requests like the redelivered AMQP messages
+/// share the same execution
+pub struct SubmissionRegistry {
+    in_flight: Mutex<HashMap<String, InFlight>>,
+    results: Cache<String, SubmissionSignal>,
+    counter: CacheCounter,
+
+    /// Unlike the results, these are persisted by their journals, so they are
+    /// neither evicted by the capacity nor lost by another restart
+    failed: Mutex<HashMap<String, FailedSubmission>>,
+}
+
+impl SubmissionRegistry {
+    pub fn new(result_ttl: Duration, max_cached_results: u64) -> Self {
+        Self {
+            in_flight: Default::default(),
+            results: Cache::builder()
+                .name(CACHE_NAME)
+                .max_capacity(max_cached_results)
+                .time_to_live(result_ttl)
+                .support_invalidation_closures()
+                .build(),
+            counter: CacheCounter::new(),
+            failed: Default::default(),
+        }
+    }
+
+    pub fn register(
+        self: &Arc<Self>,
+        id: &str,
+        status_tx: RingSender<SubmissionSignal>,
+    ) -> Registration {
+        // The lock is held while looking up the results, since the forwarder moves
+        // the final signal into the results and removes the entry under it
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(entry) = in_flight.get_mut(id) {
+            if let Some(signal) = &entry.last_signal {
+                _ = status_tx.send(signal.clone());
+            }
+            entry.subscribers.push(status_tx);
+            return Registration::Attached;
+        }
+
+        let result = self.results.get(id);
+        self.counter.record(result.is_some());
+        if let Some(signal) = result {
+            _ = status_tx.send(signal);
+            return Registration::Cached;
+        }
+
+        if let Some(failed) = self.failed.lock().unwrap().remove(id) {
+            failed.remove_journal();
+            if failed.expires_at > Utc::now() {
+                // Also answers the duplicates of this request like the completed results
+                self.results.insert(id.to_owned(), failed.signal.clone());
+                _ = status_tx.send(failed.signal);
+                return Registration::Cached;
+            }
+        }
+
+        in_flight
+            .insert(id.to_owned(), InFlight { subscribers: vec![status_tx], last_signal: None });
+
+        let (tx, rx) = ring_channel(NonZeroUsize::MIN);
+        tokio::spawn(self.clone().forward_signals(id.to_owned(), rx));
+        Registration::Started(tx)
+    }
+
+    async fn forward_signals(self: Arc<Self>, id: String, mut rx: RingReceiver<SubmissionSignal>) {
+        while let Some(signal) = rx.next().await {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let Some(entry) = in_flight.get_mut(&id) else {
+                return;
+            };
+
+            entry.subscribers.retain(|tx| tx.send(signal.clone()).is_ok());
+            match signal.ext {
+                SubmissionSignalExt::Progress(_) => entry.last_signal = Some(signal),
+                SubmissionSignalExt::Completed(_) => {
+                    self.results.insert(id.clone(), signal);
+                    in_flight.remove(&id);
+                    return;
+                }
+                // The errors are not cached, so the submission can be retried
+                SubmissionSignalExt::Error(_) => {
+                    in_flight.remove(&id);
+                    return;
+                }
+            }
+        }
+
+        self.in_flight.lock().unwrap().remove(&id);
+    }
+}
+
+impl ManagedCache for SubmissionRegistry {
+    fn name(&self) -> &'static str {
+        CACHE_NAME
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.counter.make_stats(CACHE_NAME, self.results.entry_count(), 0)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.results.iter().map(|(key, _)| key.as_ref().clone()).collect()
+    }
+
+    fn purge(&self, prefix: Option<&str>) -> u64 {
+        let Some(prefix) = prefix else {
+            let count = self.results.entry_count();
+            self.results.invalidate_all();
+            return count;
+        };
+
+        let prefix = prefix.to_owned();
+        let count = self.results.iter().filter(|(key, _)| key.starts_with(&prefix)).count();
+        _ = self.results.invalidate_entries_if(move |key, _| key.starts_with(&prefix));
+        count as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+    use chrono::{TimeDelta, Utc};
+    use futures_util::StreamExt;
+    use ring_channel::ring_channel;
+    use serde_json::Value;
+
+    use super::{FailedSubmission, Registration, SubmissionRegistry};
+    use crate::{
+        SubmissionErrorSignal, SubmissionReportSignal, SubmissionSignal, SubmissionSignalExt,
+    };
+
+    fn make_signal(completed: bool) -> SubmissionSignal {
+        let signal = SubmissionReportSignal {
+            report_at: Utc::now(),
+            report: None,
+            report_error: None,
+            status: Value::Null,
+        };
+        SubmissionSignal {
+            id: Some("foo".to_owned()),
+            ext: if completed {
+                SubmissionSignalExt::Completed(signal)
+            } else {
+                SubmissionSignalExt::Progress(signal)
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register() {
+        let registry = Arc::new(SubmissionRegistry::new(Duration::from_secs(60), 10));
+
+        let (first_tx, mut first_rx) = ring_channel(NonZeroUsize::MIN);
+        let Registration::Started(tx) = registry.register("foo", first_tx) else {
+            panic!("Expected the submission to be started");
+        };
+
+        tx.send(make_signal(false)).unwrap();
+        assert!(matches!(first_rx.next().await.unwrap().ext, SubmissionSignalExt::Progress(_)));
+
+        // The duplicate receives the last progress and the following signals
+        let (second_tx, mut second_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("foo", second_tx), Registration::Attached));
+        assert!(matches!(second_rx.next().await.unwrap().ext, SubmissionSignalExt::Progress(_)));
+
+        tx.send(make_signal(true)).unwrap();
+        assert!(matches!(first_rx.next().await.unwrap().ext, SubmissionSignalExt::Completed(_)));
+        assert!(matches!(second_rx.next().await.unwrap().ext, SubmissionSignalExt::Completed(_)));
+
+        let (third_tx, mut third_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("foo", third_tx), Registration::Cached));
+        assert!(matches!(third_rx.next().await.unwrap().ext, SubmissionSignalExt::Completed(_)));
+
+        let (other_tx, _other_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("bar", other_tx), Registration::Started(_)));
+    }
+
+    #[tokio::test]
+    async fn test_register_failed() {
+        let registry = Arc::new(SubmissionRegistry::new(Duration::from_secs(60), 10));
+        let directory = tempfile::tempdir().unwrap();
+
+        let mut journals = vec![];
+        for (id, expires_in) in [("foo", 60), ("bar", -60)] {
+            let journal = directory.path().join(format!("{id}.jsonl"));
+            std::fs::write(&journal, "").unwrap();
+            journals.push(journal.clone());
+
+            let signal = SubmissionSignal {
+                id: Some(id.to_owned()),
+                ext: SubmissionSignalExt::Error(SubmissionErrorSignal { error: "".to_owned() }),
+            };
+            let expires_at = Utc::now() + TimeDelta::seconds(expires_in);
+            registry
+                .failed
+                .lock()
+                .unwrap()
+                .insert(id.to_owned(), FailedSubmission { signal, journal, expires_at });
+        }
+
+        // The failed submission is answered and its journal is consumed
+        let (first_tx, mut first_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("foo", first_tx), Registration::Cached));
+        assert!(matches!(first_rx.next().await.unwrap().ext, SubmissionSignalExt::Error(_)));
+        assert!(!journals[0].exists());
+
+        let (second_tx, mut second_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("foo", second_tx), Registration::Cached));
+        assert!(matches!(second_rx.next().await.unwrap().ext, SubmissionSignalExt::Error(_)));
+
+        // The expired one is executed again
+        let (other_tx, _other_rx) = ring_channel(NonZeroUsize::MIN);
+        assert!(matches!(registry.register("bar", other_tx), Registration::Started(_)));
+        assert!(!journals[1].exists());
+    }
+}
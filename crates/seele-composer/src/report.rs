@@ -22,10 +22,9 @@ pub async fn apply_embeds_config(
 ) -> Result<HashMap<String, String>> {
     Ok(HashMap::from_iter({
         future::try_join_all(embeds.iter().map(|config| async move {
-            // TODO: Should we check for malicious paths?
-            let path = root.join(&config.path);
-
             async {
+                let path = config.path.resolve(root).await?;
+
                 let Ok(metadata) = fs::metadata(&path).await else {
                     if !config.ignore_if_missing {
                         bail!("Failed to open the file");
@@ -52,7 +51,7 @@ pub async fn apply_embeds_config(
                 anyhow::Ok(Some((config.field.clone(), content)))
             }
             .await
-            .with_context(|| format!("Error handling the file: {}", path.display()))
+            .with_context(|| format!("Error handling the file: {}", config.path))
         }))
         .await?
         .into_iter()
@@ -68,9 +67,8 @@ pub async fn apply_uploads_config(
     uploads: &[SubmissionReportUploadConfig],
 ) -> Result<()> {
     let results = future::join_all(uploads.iter().map(|config| async move {
-        let path = root.join(&config.path);
-
         async {
+            let path = config.path.resolve(root).await?;
             let Ok(metadata) = fs::metadata(&path).await else {
                 if !config.ignore_if_missing {
                     bail!("Failed to open the file");
@@ -98,7 +96,7 @@ pub async fn apply_uploads_config(
             Ok(())
         }
         .await
-        .with_context(|| format!("Error uploading the file: {}", path.display()))
+        .with_context(|| format!("Error uploading the file: {}", config.path))
     }))
     .await;
 
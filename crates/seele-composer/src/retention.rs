@@ -0,0 +1,265 @@
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{sync::Mutex, task::spawn_blocking, time};
+use tracing::{debug, error, info};
+
+use crate::conf::{self, RetentionWhen};
+
+static RETENTION: LazyLock<Retention> = LazyLock::new(|| {
+    let config = &conf::CONFIG.composer.retention;
+    Retention::new(
+        conf::PATHS.retained.clone(),
+        Duration::from_secs(config.ttl_secs),
+        config.max_count,
+        config.max_size_mib * 1024 * 1024,
+    )
+});
+
+/// Keep the directory of the handled submission if the retention policy says
+/// so, otherwise remove it
+pub async fn retain_or_remove(id: &str, submission_root: &Path, failed: bool) {
+    let retain = match conf::CONFIG.composer.retention.when {
+        RetentionWhen::Never => false,
+        RetentionWhen::Error => failed,
+        RetentionWhen::Always => true,
+    };
+
+    if retain {
+        match RETENTION.retain(id, submission_root).await {
+            Ok(()) => return,
+            Err(err) => error!("Error retaining the submission directory: {err:#}"),
+        }
+    }
+
+    _ = tokio::fs::remove_dir_all(submission_root).await;
+}
+
+/// Remove the expired directories periodically, since they are otherwise only
+/// removed when another one is retained
+pub async fn prune_periodically() {
+    if conf::CONFIG.composer.retention.when == RetentionWhen::Never {
+        return;
+    }
+
+    let mut interval = time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        if let Err(err) = RETENTION.prune().await {
+            error!("Error pruning the retained submissions: {err:#}");
+        }
+    }
+}
+
+pub async fn list_retained_submissions() -> Result<Vec<RetainedSubmission>> {
+    RETENTION.list().await
+}
+
+/// Path of the retained directory of the submission
+pub async fn find_retained_submission(id: &str) -> Result<Option<PathBuf>> {
+    RETENTION.find(id).await
+}
+
+pub async fn remove_retained_submission(id: &str) -> Result<bool> {
+    RETENTION.remove(id).await
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetainedSubmission {
+    pub id: String,
+    pub retained_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+/// Moves the directories of the submissions into the retention directory, which
+/// are named after the submission ids, and removes them once exceeding any of
+/// the limits. The retention time is the modification time of the directory,
+/// so that the retained ones survive the restarts.
+pub struct Retention {
+    directory: PathBuf,
+    ttl: Duration,
+    max_count: usize,
+    max_size_bytes: u64,
+
+    /// Serializes the operations on the retention directory
+    lock: Mutex<()>,
+}
+
+impl Retention {
+    pub fn new(directory: PathBuf, ttl: Duration, max_count: usize, max_size_bytes: u64) -> Self {
+        Self { directory, ttl, max_count, max_size_bytes, lock: Mutex::new(()) }
+    }
+
+    pub async fn retain(&self, id: &str, submission_root: &Path) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let target = self.directory.join(id);
+        let submission_root = submission_root.to_owned();
+        spawn_blocking(move || -> Result<()> {
+            if target.exists() {
+                fs::remove_dir_all(&target)
+                    .context("Error removing the directory retained with the same id")?;
+            }
+            fs::rename(&submission_root, &target).context("Error moving the directory")?;
+            File::open(&target)
+                .and_then(|file| file.set_modified(SystemTime::now()))
+                .context("Error updating the modification time")
+        })
+        .await??;
+
+        info!(seele.submission.id = id, "Retained the submission directory");
+        self.prune_locked().await
+    }
+
+    pub async fn prune(&self) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        self.prune_locked().await
+    }
+
+    async fn prune_locked(&self) -> Result<()> {
+        let directory = self.directory.clone();
+        let (ttl, max_count, max_size_bytes) = (self.ttl, self.max_count, self.max_size_bytes);
+        spawn_blocking(move || -> Result<()> {
+            let now = Utc::now();
+            let mut total_size = 0;
+            for (i, submission) in scan(&directory)?.into_iter().enumerate() {
+                let expired = (now - submission.retained_at).to_std().unwrap_or_default() > ttl;
+                total_size += submission.size_bytes;
+                if i >= max_count || expired || total_size > max_size_bytes {
+                    debug!(seele.submission.id = %submission.id, "Removing the retained submission");
+                    fs::remove_dir_all(directory.join(&submission.id))
+                        .context("Error removing the retained submission")?;
+                    total_size -= submission.size_bytes;
+                }
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn list(&self) -> Result<Vec<RetainedSubmission>> {
+        let _guard = self.lock.lock().await;
+        let directory = self.directory.clone();
+        spawn_blocking(move || scan(&directory)).await?
+    }
+
+    pub async fn find(&self, id: &str) -> Result<Option<PathBuf>> {
+        // Looks up the listed ones, since the id comes from the requests
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|submission| submission.id == id)
+            .map(|submission| self.directory.join(submission.id)))
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        let Some(path) = self.find(id).await? else {
+            return Ok(false);
+        };
+
+        let _guard = self.lock.lock().await;
+        tokio::fs::remove_dir_all(path).await.context("Error removing the retained submission")?;
+        Ok(true)
+    }
+}
+
+/// List the retained submissions, the newest first
+fn scan(directory: &Path) -> Result<Vec<RetainedSubmission>> {
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut submissions = vec![];
+    for entry in fs::read_dir(directory).context("Error reading the retention directory")? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_dir() {
+            continue;
+        }
+
+        submissions.push(RetainedSubmission {
+            id: entry.file_name().to_string_lossy().into_owned(),
+            retained_at: metadata.modified()?.into(),
+            size_bytes: directory_size(&entry.path()),
+        });
+    }
+
+    submissions.sort_by_key(|submission| Reverse(submission.retained_at));
+    Ok(submissions)
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => {
+                entry.metadata().map(|metadata| metadata.len()).unwrap_or_default()
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use super::Retention;
+
+    fn make_submission(root: &Path, id: &str, size: usize) -> PathBuf {
+        let path = root.join("submissions").join(id);
+        fs::create_dir_all(path.join("nested")).unwrap();
+        fs::write(path.join("nested").join("data"), vec![0; size]).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let directory = root.join("retained");
+        fs::create_dir_all(&directory).unwrap();
+        let retention = Retention::new(directory.clone(), Duration::from_secs(60), 2, 100);
+
+        for (id, size) in [("foo", 10), ("bar", 20), ("baz", 30)] {
+            let path = make_submission(root, id, size);
+            retention.retain(id, &path).await.unwrap();
+            assert!(!path.exists());
+        }
+
+        // Only the last two are kept
+        let ids = |submissions: Vec<super::RetainedSubmission>| {
+            submissions.into_iter().map(|submission| submission.id).collect::<Vec<_>>()
+        };
+        let submissions = retention.list().await.unwrap();
+        assert_eq!(submissions[0].size_bytes, 30);
+        assert_eq!(ids(submissions), ["baz", "bar"]);
+
+        // The older ones are removed to fit the size cap
+        let path = make_submission(root, "qux", 80);
+        retention.retain("qux", &path).await.unwrap();
+        assert_eq!(ids(retention.list().await.unwrap()), ["qux"]);
+
+        assert!(retention.find("qux").await.unwrap().is_some());
+        assert!(retention.find("../retained").await.unwrap().is_none());
+        assert!(retention.remove("qux").await.unwrap());
+        assert!(retention.list().await.unwrap().is_empty());
+    }
+}
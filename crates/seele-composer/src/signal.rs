@@ -3,7 +3,7 @@ use serde_json::Value;
 
 use crate::entities::UtcTimestamp;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SubmissionSignal {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -12,7 +12,7 @@ pub struct SubmissionSignal {
     pub ext: SubmissionSignalExt,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SubmissionSignalExt {
     Progress(SubmissionReportSignal),
@@ -20,12 +20,12 @@ pub enum SubmissionSignalExt {
     Completed(SubmissionReportSignal),
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SubmissionErrorSignal {
     pub error: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SubmissionReportSignal {
     pub report_at: UtcTimestamp,
 
@@ -0,0 +1,236 @@
+use std::{
+    fmt::{self, Display},
+    mem,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use serde_path_to_error::Segment;
+
+use crate::{
+    entities::{
+        ActionTaskConfig, SubmissionConfig, TaskConfig, add_file, noop, run_container,
+        run_container::run_judge::{compile, run},
+    },
+    format::DataFormat,
+};
+
+/// Fields shared by all kinds of tasks
+const TASK_FIELDS: [&str; 5] = ["progress", "report", "tags", "when", "needs"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationError {
+    /// Path of the invalid value, e.g.
+    /// `steps.run.parallel[3].limits.memory_kib`
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { path: path.into(), message: message.into() }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Validate the submission piece by piece, since the errors inside untagged or
+/// flattened values lose their paths when the submission is parsed as a whole
+pub fn validate_submission(format: DataFormat, data: &[u8]) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    match format.deserialize::<Value>(data) {
+        Err(err) => errors.push(ValidationError::new("", format!("{err:#}"))),
+        Ok(Value::Object(mut submission)) => {
+            let steps = submission
+                .get_mut("steps")
+                .map(|steps| mem::replace(steps, Value::Object(Map::new())));
+            check::<SubmissionConfig>("", Value::Object(submission), &mut errors);
+            if let Some(steps) = steps {
+                validate_steps("steps", &steps, &mut errors);
+            }
+        }
+        Ok(_) => errors.push(ValidationError::new("", "Expected a map")),
+    }
+
+    errors
+}
+
+fn validate_steps(path: &str, value: &Value, errors: &mut Vec<ValidationError>) {
+    let Some(tasks) = value.as_object() else {
+        errors.push(ValidationError::new(path, "Expected a map of tasks"));
+        return;
+    };
+
+    if tasks.is_empty() {
+        errors.push(ValidationError::new(path, "Empty steps provided"));
+    }
+
+    for (name, task) in tasks {
+        validate_task(&format!("{path}.{name}"), task, errors);
+    }
+}
+
+fn validate_task(path: &str, value: &Value, errors: &mut Vec<ValidationError>) {
+    let Some(task) = value.as_object() else {
+        errors.push(ValidationError::new(path, "Expected a task"));
+        return;
+    };
+
+    let (mut common, ext): (Map<_, _>, Map<_, _>) =
+        task.clone().into_iter().partition(|(key, _)| TASK_FIELDS.contains(&key.as_str()));
+
+    // Check the shared fields with a placeholder of the sequence task
+    common.insert("steps".to_owned(), Value::Object(Map::new()));
+    check::<TaskConfig>(path, Value::Object(common), errors);
+
+    if let Some(steps) = ext.get("steps") {
+        validate_steps(&format!("{path}.steps"), steps, errors);
+    } else if let Some(parallel) = ext.get("parallel") {
+        match parallel {
+            Value::Array(tasks) => tasks.iter().enumerate().for_each(|(i, task)| {
+                validate_task(&format!("{path}.parallel[{i}]"), task, errors)
+            }),
+            Value::Object(tasks) => tasks.iter().for_each(|(name, task)| {
+                validate_task(&format!("{path}.parallel.{name}"), task, errors)
+            }),
+            _ => errors.push(ValidationError::new(
+                format!("{path}.parallel"),
+                "Expected a list or a map of tasks",
+            )),
+        }
+    } else if ext.contains_key("action") {
+        validate_action(path, ext, errors);
+    } else {
+        errors.push(ValidationError::new(path, "Expected one of `steps`, `parallel` or `action`"));
+    }
+}
+
+fn validate_action(path: &str, mut action: Map<String, Value>, errors: &mut Vec<ValidationError>) {
+    let name = action.get("action").and_then(Value::as_str).unwrap_or_default().to_owned();
+
+    // The configs flattening the container config are checked in two steps
+    let container_fields = |fields: &[&str]| {
+        let config = action
+            .iter()
+            .filter(|(key, _)| !fields.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Value::Object(config)
+    };
+
+    match name.as_str() {
+        "seele/noop@1" => check::<noop::Config>(path, Value::Object(action), errors),
+        "seele/add-file@1" => check::<add_file::Config>(path, Value::Object(action), errors),
+        "seele/run-container@1" => {
+            check::<run_container::Config>(path, Value::Object(action), errors)
+        }
+        "seele/run-judge/compile@1" => {
+            let config = container_fields(&["action", "sources", "saves", "cache"]);
+            check::<run_container::Config>(path, config, errors)
+                && check::<compile::Config>(path, Value::Object(action), errors)
+        }
+        "seele/run-judge/run@1" => {
+            let config = container_fields(&["action", "files"]);
+            check::<run_container::Config>(path, config, errors)
+                && check::<run::Config>(path, Value::Object(action), errors)
+        }
+        _ => {
+            // Reports the unknown action along with the known ones
+            action.insert("action".to_owned(), Value::String(name));
+            check::<ActionTaskConfig>(path, Value::Object(action), errors)
+        }
+    };
+}
+
+/// Deserialize the value as `T`, recording the error with the full path
+fn check<T: DeserializeOwned>(path: &str, value: Value, errors: &mut Vec<ValidationError>) -> bool {
+    let Err(err) = serde_path_to_error::deserialize::<_, T>(value) else {
+        return true;
+    };
+
+    let mut full_path = path.to_owned();
+    for segment in err.path() {
+        match segment {
+            Segment::Seq { index } => full_path.push_str(&format!("[{index}]")),
+            segment => {
+                if !full_path.is_empty() {
+                    full_path.push('.');
+                }
+                full_path.push_str(&segment.to_string());
+            }
+        }
+    }
+
+    errors.push(ValidationError::new(full_path, err.into_inner().to_string()));
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use seele_shared::entities::submission_schema;
+
+    use super::{TASK_FIELDS, validate_submission};
+    use crate::format::DataFormat;
+
+    #[test]
+    fn test_task_fields() {
+        // The flattened fields are not the properties of the task
+        let schema = serde_json::to_value(submission_schema()).unwrap();
+        let fields = schema["$defs"]["TaskConfig"]["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, TASK_FIELDS);
+    }
+
+    #[test]
+    fn test_validate_submission() {
+        let submission = r#"
+            steps:
+              prepare:
+                action: "seele/add-file@1"
+                files:
+                  - path: "main.cpp"
+                    plain: ""
+                    size: "large"
+              run:
+                tags: 1
+                parallel:
+                  - action: "seele/run-container@1"
+                    image: "gcc:11-bullseye"
+                    command: "main"
+                    limits:
+                      memory_kib: "1k"
+                  - action: "seele/unknown@1"
+                  - steps: {}
+              report:
+                foo: "bar"
+        "#;
+
+        let errors = validate_submission(DataFormat::Yaml, submission.as_bytes())
+            .into_iter()
+            .map(|err| err.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "steps.prepare.files[0].size",
+                "steps.run.tags",
+                "steps.run.parallel[0].limits.memory_kib",
+                "steps.run.parallel[1].action",
+                "steps.run.parallel[2].steps",
+                "steps.report",
+            ]
+        );
+    }
+}
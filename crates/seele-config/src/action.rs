@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use http_cache::CacheMode;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::image::OciImage;
 
@@ -22,6 +26,52 @@ pub struct ActionAddFileConfig {
 
     #[serde(default = "default_cache_ttl_hour")]
     pub cache_ttl_hour: u64,
+
+    #[serde(default = "default_extract_max_size_mib")]
+    pub extract_max_size_mib: u64,
+
+    #[serde(default = "default_extract_max_entries")]
+    pub extract_max_entries: u64,
+
+    /// Directories from which local files can be added. Local files are
+    /// rejected if this is empty.
+    #[serde(default)]
+    pub local_roots: Vec<PathBuf>,
+
+    #[serde(default)]
+    pub local_strategy: LocalFileStrategy,
+
+    #[serde(default = "default_store_size_mib")]
+    pub store_size_mib: u64,
+
+    /// The S3-compatible object storage to add files from
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    pub endpoint: Url,
+
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+
+    pub access_key_id: String,
+
+    pub secret_access_key: String,
+
+    /// Use `bucket.endpoint/key` instead of `endpoint/bucket/key`
+    #[serde(default)]
+    pub virtual_hosted_style: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalFileStrategy {
+    #[default]
+    Copy,
+    HardLink,
+    Reflink,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -54,6 +104,12 @@ impl Default for ActionAddFileConfig {
             cache_strategy: default_cache_strategy(),
             cache_size_mib: default_cache_size_mib(),
             cache_ttl_hour: default_cache_ttl_hour(),
+            extract_max_size_mib: default_extract_max_size_mib(),
+            extract_max_entries: default_extract_max_entries(),
+            local_roots: Default::default(),
+            local_strategy: Default::default(),
+            store_size_mib: default_store_size_mib(),
+            s3: None,
         }
     }
 }
@@ -73,6 +129,26 @@ const fn default_cache_ttl_hour() -> u64 {
     24 * 3
 }
 
+#[inline]
+const fn default_extract_max_size_mib() -> u64 {
+    1024
+}
+
+#[inline]
+const fn default_extract_max_entries() -> u64 {
+    16384
+}
+
+#[inline]
+const fn default_store_size_mib() -> u64 {
+    1024
+}
+
+#[inline]
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ActionRunContainerConfig {
     #[serde(default = "default_pull_image_timeout_seconds")]
@@ -98,6 +174,10 @@ pub struct ActionRunContainerConfig {
 
     #[serde(default = "default_cache_ttl_hour")]
     pub cache_ttl_hour: u64,
+
+    /// Network modes the containers are allowed to use
+    #[serde(default = "default_allowed_network_modes")]
+    pub allowed_network_modes: Vec<NetworkMode>,
 }
 
 impl Default for ActionRunContainerConfig {
@@ -111,6 +191,7 @@ impl Default for ActionRunContainerConfig {
             preload_images: Default::default(),
             cache_size_mib: default_cache_size_mib(),
             cache_ttl_hour: default_cache_ttl_hour(),
+            allowed_network_modes: default_allowed_network_modes(),
         }
     }
 }
@@ -142,3 +223,31 @@ fn default_userns_user() -> String {
 fn default_userns_gid() -> u32 {
     uzers::get_effective_gid()
 }
+
+#[inline]
+fn default_allowed_network_modes() -> Vec<NetworkMode> {
+    vec![NetworkMode::None, NetworkMode::Loopback]
+}
+
+/// Every container has its own network namespace, which differs in the
+/// interfaces brought up in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkMode {
+    /// No interfaces are up
+    #[default]
+    None,
+
+    /// Only the loopback interface is up, for the programs talking to the
+    /// servers started by themselves on localhost
+    Loopback,
+}
+
+impl std::fmt::Display for NetworkMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Loopback => write!(f, "loopback"),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use serde::Deserialize;
+
+/// The admin endpoint has no authentication, so it listens on its own address
+/// which is only reachable locally by default
+#[derive(Debug, Deserialize)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_address")]
+    pub address: IpAddr,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self { enabled: false, address: default_address(), port: default_port() }
+    }
+}
+
+#[inline]
+const fn default_address() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))
+}
+
+#[inline]
+const fn default_port() -> u16 {
+    50001
+}
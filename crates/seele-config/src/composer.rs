@@ -1,4 +1,141 @@
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Default)]
-pub struct ComposerConfig;
+#[derive(Debug, Deserialize)]
+pub struct ComposerConfig {
+    /// How long the final signals of the completed submissions are kept for
+    /// the resubmissions with the same ids
+    #[serde(default = "default_result_ttl_secs")]
+    pub result_ttl_secs: u64,
+
+    #[serde(default = "default_max_cached_results")]
+    pub max_cached_results: u64,
+
+    /// How many submissions are handled at the same time, beyond which the
+    /// received submissions wait in the queue
+    #[serde(default = "default_max_in_flight_submissions")]
+    pub max_in_flight_submissions: usize,
+
+    /// How many received submissions wait in the queue to be admitted by their
+    /// priorities, beyond which the exchanges stop accepting new ones
+    #[serde(default = "default_max_queued_submissions")]
+    pub max_queued_submissions: usize,
+
+    /// What to do with the submissions interrupted by the last run when they
+    /// are received again
+    #[serde(default)]
+    pub recovery_policy: RecoveryPolicy,
+
+    /// How long the journals of the submissions failed by the `fail` recovery
+    /// policy are kept, across the restarts, until they are received again
+    #[serde(default = "default_recovery_ttl_secs")]
+    pub recovery_ttl_secs: u64,
+
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryPolicy {
+    /// Respond with an error signal, so that a submission crashing seele is not
+    /// executed over and over again
+    #[default]
+    Fail,
+
+    /// Execute the submission again from the beginning
+    Retry,
+}
+
+impl Default for ComposerConfig {
+    fn default() -> Self {
+        Self {
+            result_ttl_secs: default_result_ttl_secs(),
+            max_cached_results: default_max_cached_results(),
+            max_in_flight_submissions: default_max_in_flight_submissions(),
+            max_queued_submissions: default_max_queued_submissions(),
+            recovery_policy: Default::default(),
+            recovery_ttl_secs: default_recovery_ttl_secs(),
+            retention: Default::default(),
+        }
+    }
+}
+
+/// Which directories of the handled submissions are kept for debugging, which
+/// are removed once exceeding any of the limits, the oldest first
+#[derive(Debug, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub when: RetentionWhen,
+
+    #[serde(default = "default_retention_ttl_secs")]
+    pub ttl_secs: u64,
+
+    #[serde(default = "default_retention_max_count")]
+    pub max_count: usize,
+
+    #[serde(default = "default_retention_max_size_mib")]
+    pub max_size_mib: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            when: Default::default(),
+            ttl_secs: default_retention_ttl_secs(),
+            max_count: default_retention_max_count(),
+            max_size_mib: default_retention_max_size_mib(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionWhen {
+    #[default]
+    Never,
+
+    /// Keep the directories of the submissions ending with error signals
+    Error,
+
+    Always,
+}
+
+#[inline]
+const fn default_result_ttl_secs() -> u64 {
+    60 * 5
+}
+
+#[inline]
+const fn default_recovery_ttl_secs() -> u64 {
+    60 * 60 * 24
+}
+
+#[inline]
+const fn default_max_cached_results() -> u64 {
+    1000
+}
+
+#[inline]
+const fn default_retention_ttl_secs() -> u64 {
+    60 * 60
+}
+
+#[inline]
+const fn default_retention_max_count() -> usize {
+    20
+}
+
+#[inline]
+const fn default_retention_max_size_mib() -> u64 {
+    1024
+}
+
+#[inline]
+const fn default_max_in_flight_submissions() -> usize {
+    256
+}
+
+#[inline]
+const fn default_max_queued_submissions() -> usize {
+    64
+}
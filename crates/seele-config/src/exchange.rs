@@ -23,6 +23,14 @@ pub struct HttpExchangeConfig {
 
     #[serde(default = "default_max_body_size")]
     pub max_body_size_bytes: u64,
+
+    #[serde(default = "default_max_attachments_size")]
+    pub max_attachments_size_bytes: u64,
+
+    /// The `Retry-After` sent along with the rejections when the composer queue
+    /// is full
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -104,3 +112,13 @@ const fn default_http_address() -> IpAddr {
 const fn default_max_body_size() -> u64 {
     8 * 1024 * 1024
 }
+
+#[inline]
+const fn default_max_attachments_size() -> u64 {
+    1024 * 1024 * 1024
+}
+
+#[inline]
+const fn default_retry_after_secs() -> u64 {
+    5
+}
@@ -4,13 +4,22 @@ use indexmap::IndexMap;
 use serde::Deserialize;
 use tracing_subscriber::filter::LevelFilter;
 
-pub use self::{action::*, env::*, exchange::*, image::*, path::*};
+pub use self::{
+    action::*,
+    composer::{RecoveryPolicy, RetentionWhen},
+    env::*,
+    exchange::*,
+    image::*,
+    path::*,
+    tenant::*,
+};
 use self::{
-    composer::ComposerConfig, healthz::HealthzConfig, http::HttpConfig, telemetry::TelemetryConfig,
-    worker::WorkerConfig,
+    admin::AdminConfig, composer::ComposerConfig, healthz::HealthzConfig, http::HttpConfig,
+    telemetry::TelemetryConfig, worker::WorkerConfig,
 };
 
 mod action;
+mod admin;
 mod composer;
 pub mod env;
 mod exchange;
@@ -19,6 +28,7 @@ mod http;
 mod image;
 mod path;
 mod telemetry;
+mod tenant;
 mod worker;
 
 pub static CONFIG: LazyLock<SeeleConfig> = LazyLock::new(|| {
@@ -55,6 +65,9 @@ pub struct SeeleConfig {
     #[serde(default)]
     pub healthz: HealthzConfig,
 
+    #[serde(default)]
+    pub admin: AdminConfig,
+
     #[serde(default)]
     pub http: HttpConfig,
 
@@ -66,25 +79,24 @@ pub struct SeeleConfig {
 
     #[serde(default)]
     pub worker: WorkerConfig,
+
+    /// Configurations of the tenants sharing the runners, keyed by their names
+    #[serde(default)]
+    pub tenants: IndexMap<String, TenantConfig>,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LogLevel {
     Trace,
     Debug,
     Info,
+    #[default]
     Warn,
     Error,
     Off,
 }
 
-impl Default for LogLevel {
-    fn default() -> Self {
-        Self::Warn
-    }
-}
-
 impl From<LogLevel> for LevelFilter {
     fn from(val: LogLevel) -> Self {
         match val {
@@ -11,6 +11,10 @@ pub struct SeelePaths {
     pub images: PathBuf,
     pub temp: PathBuf,
     pub submissions: PathBuf,
+    pub store: PathBuf,
+    pub attachments: PathBuf,
+    pub journal: PathBuf,
+    pub retained: PathBuf,
 }
 
 impl SeelePaths {
@@ -28,4 +32,8 @@ pub static PATHS: LazyLock<SeelePaths> = LazyLock::new(|| SeelePaths {
     images: CONFIG.paths.root.join("images"),
     temp: CONFIG.paths.root.join("temp"),
     submissions: CONFIG.paths.tmp.join("seele").join("submissions"),
+    store: CONFIG.paths.tmp.join("seele").join("store"),
+    attachments: CONFIG.paths.tmp.join("seele").join("attachments"),
+    journal: CONFIG.paths.root.join("journal"),
+    retained: CONFIG.paths.tmp.join("seele").join("retained"),
 });
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// The submissions without tenants belong to this one
+pub const DEFAULT_TENANT: &str = "default";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+    /// The share of the runners when the tenants compete for them
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+
+    /// The maximum count of the runners used by the tenant at the same time
+    #[serde(default)]
+    pub max_runners: Option<usize>,
+}
+
+impl Default for TenantConfig {
+    fn default() -> Self {
+        Self { weight: default_weight(), max_runners: None }
+    }
+}
+
+#[inline]
+const fn default_weight() -> u32 {
+    1
+}
@@ -7,7 +7,7 @@ use std::{
 
 use anyhow::{Context, Result, bail};
 use futures_util::StreamExt;
-use lapin::{Channel, ChannelState, Connection, message::Delivery};
+use lapin::{BasicProperties, Channel, ChannelState, Connection, message::Delivery};
 use ring_channel::ring_channel;
 use tokio::{
     sync::{Mutex, mpsc},
@@ -18,8 +18,11 @@ use tracing::{error, info, warn};
 use triggered::Listener;
 
 use crate::{
-    composer::{ComposerQueueItem, ComposerQueueTx, SubmissionSignal, SubmissionSignalExt},
+    composer::{
+        ComposerQueueItem, ComposerQueueTx, DataFormat, SubmissionSignal, SubmissionSignalExt,
+    },
     conf::{self, AmqpExchangeConfig, AmqpExchangeReportConfig},
+    shared::entities::SubmissionPriority,
 };
 
 static STATUS_MAP: LazyLock<Mutex<HashMap<String, bool>>> = LazyLock::new(Default::default);
@@ -146,8 +149,14 @@ async fn do_consume(
         .await
         .context("Error declaring the report exchange")?;
 
+    // The broker stops delivering once this many submissions are unacknowledged,
+    // which are acknowledged after being completed. The queued ones are counted
+    // as well, so that the composer admits them by their priorities.
+    let prefetch_count = (conf::CONFIG.composer.max_in_flight_submissions
+        + conf::CONFIG.composer.max_queued_submissions)
+        .min(u16::MAX.into());
     channel
-        .basic_qos(conf::CONFIG.thread_counts.runner.try_into()?, Default::default())
+        .basic_qos(prefetch_count.try_into()?, Default::default())
         .await
         .context("Error setting channel qos")?;
 
@@ -197,11 +206,36 @@ async fn handle_delivery(
     channel: Arc<Channel>,
     config: Arc<AmqpExchangeReportConfig>,
 ) -> Result<()> {
-    let config_yaml = String::from_utf8(delivery.data.clone())?;
+    let properties = &delivery.properties;
+    let format = properties
+        .content_type()
+        .as_ref()
+        .and_then(|content_type| DataFormat::from_mime(content_type.as_str()))
+        .unwrap_or_default();
+
+    // Signals are sent as JSON unless MessagePack is accepted
+    let signal_format = match properties
+        .headers()
+        .as_ref()
+        .and_then(|headers| headers.inner().get("accept"))
+        .and_then(|accept| accept.as_long_string())
+        .and_then(|accept| DataFormat::from_accept(&accept.to_string()))
+    {
+        Some(DataFormat::MessagePack) => DataFormat::MessagePack,
+        _ => DataFormat::Json,
+    };
+
     let (status_tx, mut status_rx) =
         ring_channel::<SubmissionSignal>(NonZeroUsize::try_from(1).unwrap());
 
-    tx.send(ComposerQueueItem { config_yaml, status_tx }).await?;
+    tx.send(ComposerQueueItem {
+        config: delivery.data.clone(),
+        format,
+        priority: properties.priority().map(SubmissionPriority::from_amqp),
+        attachments: HashMap::new(),
+        status_tx,
+    })
+    .await?;
 
     tokio::spawn({
         let channel = channel.clone();
@@ -221,9 +255,8 @@ async fn handle_delivery(
                 };
 
                 let result = async {
-                    let mut data = Vec::with_capacity(4096);
-                    serde_json::to_writer(&mut data, &signal)
-                        .context("Error serializing the report")?;
+                    let data =
+                        signal_format.serialize(&signal).context("Error serializing the report")?;
 
                     channel
                         .basic_publish(
@@ -231,7 +264,8 @@ async fn handle_delivery(
                             routing_key,
                             Default::default(),
                             &data,
-                            Default::default(),
+                            BasicProperties::default()
+                                .with_content_type(signal_format.mime().into()),
                         )
                         .await
                         .context("Error publishing the report")?
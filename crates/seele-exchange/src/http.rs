@@ -1,39 +1,82 @@
-use std::{convert::Infallible, net::SocketAddr, num::NonZeroUsize, time::Duration};
+use std::{
+    collections::HashMap, convert::Infallible, net::SocketAddr, num::NonZeroUsize, path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use axum::{
-    Router,
+    Json, Router,
     body::{Body, HttpBody, to_bytes},
-    extract::Request,
-    http::StatusCode,
+    extract::{DefaultBodyLimit, FromRequest, Multipart, Request},
+    http::{
+        HeaderValue, StatusCode,
+        header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER},
+    },
     response::{IntoResponse, Response},
-    routing::any,
+    routing::{any, get},
 };
 use futures_util::StreamExt;
 use ring_channel::ring_channel;
-use tokio::{net::TcpListener, time::sleep};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    net::TcpListener,
+    sync::mpsc::error::TrySendError,
+    time::sleep,
+};
 use tokio_graceful_shutdown::SubsystemHandle;
 use tracing::{error, info};
 
 use crate::{
-    composer::{ComposerQueueItem, ComposerQueueTx, SubmissionSignal, SubmissionSignalExt},
-    conf::HttpExchangeConfig,
+    composer::{
+        self, ComposerQueueItem, ComposerQueueTx, DataFormat, DryRunReport, SubmissionSignal,
+        SubmissionSignalExt,
+    },
+    conf::{self, HttpExchangeConfig},
+    shared::entities,
 };
 
+#[derive(Debug, Clone, Copy)]
+struct BodyLimits {
+    max_body_size_bytes: u64,
+    max_attachments_size_bytes: u64,
+}
+
+/// Seconds after which the rejected submissions can be resent
+#[derive(Debug, Clone, Copy)]
+struct RetryAfter(u64);
+
 pub async fn run(
     name: &str,
     handle: SubsystemHandle,
     tx: ComposerQueueTx,
     config: &HttpExchangeConfig,
 ) -> Result<()> {
-    let app = Router::new().route(
-        "/",
-        any({
-            let tx = tx.clone();
-            let max_body_size_bytes = config.max_body_size_bytes;
-            move |request: Request| handle_submission_request(request, tx, max_body_size_bytes)
-        }),
-    );
+    let limits = BodyLimits {
+        max_body_size_bytes: config.max_body_size_bytes,
+        max_attachments_size_bytes: config.max_attachments_size_bytes,
+    };
+    let retry_after = RetryAfter(config.retry_after_secs);
+    let app = Router::new()
+        .route(
+            "/",
+            any({
+                let tx = tx.clone();
+                move |request: Request| handle_submission_request(request, tx, limits, retry_after)
+            }),
+        )
+        .route(
+            "/dry-run",
+            any(move |request: Request| {
+                handle_dry_run_request(request, limits.max_body_size_bytes)
+            }),
+        )
+        .route("/schema", get(|| async { Json(entities::submission_schema()) }))
+        .layer(DefaultBodyLimit::max(
+            (limits.max_body_size_bytes + limits.max_attachments_size_bytes)
+                .try_into()
+                .unwrap_or(usize::MAX),
+        ));
 
     let addr = SocketAddr::from((config.address, config.port));
     let listener = TcpListener::bind(addr).await?;
@@ -52,28 +95,36 @@ pub async fn run(
     Ok(())
 }
 
-fn serialize(debug: bool, signal: &SubmissionSignal) -> String {
-    let result =
-        if debug { serde_json::to_string_pretty(signal) } else { serde_json::to_string(signal) };
+fn serialize(format: DataFormat, debug: bool, signal: &SubmissionSignal) -> Vec<u8> {
+    let result = match format {
+        DataFormat::Json if debug => serde_json::to_vec_pretty(signal).map_err(Into::into),
+        _ => format.serialize(signal),
+    };
     match result {
         Err(err) => {
             error!("Error serializing the value: {:#}", err);
-            "".to_string()
+            vec![]
+        }
+        Ok(mut data) => {
+            if format == DataFormat::Json {
+                data.push(b'\n');
+            }
+            data
         }
-        Ok(json) => format!("{json}\n"),
     }
 }
 
 async fn handle_submission_request(
     request: Request,
     tx: ComposerQueueTx,
-    max_body_size_bytes: u64,
-) -> impl IntoResponse {
-    match handle_submission_request_inner(request, tx, max_body_size_bytes).await {
-        Ok(response) => (StatusCode::OK, response),
+    limits: BodyLimits,
+    retry_after: RetryAfter,
+) -> Response {
+    match handle_submission_request_inner(request, tx, limits, retry_after).await {
+        Ok(response) => response,
         Err(err) => {
             error!("Error handling the submission request: {:#}", err);
-            (StatusCode::INTERNAL_SERVER_ERROR, Response::new(Body::from(err.to_string())))
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
         }
     }
 }
@@ -81,31 +132,175 @@ async fn handle_submission_request(
 async fn handle_submission_request_inner(
     request: Request,
     tx: ComposerQueueTx,
-    max_body_size_bytes: u64,
+    limits: BodyLimits,
+    retry_after: RetryAfter,
 ) -> Result<Response> {
-    {
-        let body_size = request.body().size_hint().upper().unwrap_or(max_body_size_bytes + 1);
-        if body_size > max_body_size_bytes {
-            bail!("The size of the request body exceeds the limit: {}", body_size);
-        }
+    // Rejects early to avoid receiving the body and the attachments in vain
+    if tx.capacity() == 0 {
+        return Ok(too_many_requests(retry_after));
     }
 
     let show_progress = matches!(request.uri().query(), Some(query) if query.contains("progress"));
     let debug = matches!(request.uri().query(), Some(query) if query.contains("debug"));
-    let config_yaml =
-        { String::from_utf8(to_bytes(request.into_body(), usize::MAX).await?.to_vec())? };
+    let (content_type, signal_format) = negotiate_formats(&request);
+    let (config, format, attachments) = if content_type.starts_with("multipart/form-data") {
+        let multipart = Multipart::from_request(request, &()).await?;
+        read_multipart(multipart, limits).await?
+    } else {
+        let config = read_body(request, limits.max_body_size_bytes).await?;
+        let format = DataFormat::from_mime(&content_type).unwrap_or_default();
+        (config, format, HashMap::new())
+    };
+
     let (status_tx, status_rx) = ring_channel(NonZeroUsize::try_from(1).unwrap());
-    tx.send(ComposerQueueItem { config_yaml, status_tx }).await?;
+    let item = ComposerQueueItem { config, format, priority: None, attachments, status_tx };
+    match tx.try_send(item) {
+        Ok(()) => {}
+        Err(TrySendError::Full(item)) => {
+            for path in item.attachments.values() {
+                _ = fs::remove_file(path).await;
+            }
+            return Ok(too_many_requests(retry_after));
+        }
+        Err(TrySendError::Closed(_)) => bail!("The composer queue is closed"),
+    }
 
     let stream = status_rx.map(move |signal| {
-        type CallbackResult = Result<String, Infallible>;
+        type CallbackResult = Result<Vec<u8>, Infallible>;
 
         if !show_progress && matches!(signal.ext, SubmissionSignalExt::Progress { .. }) {
-            return CallbackResult::Ok("".to_string());
+            return CallbackResult::Ok(vec![]);
         }
 
-        CallbackResult::Ok(serialize(debug, &signal))
+        CallbackResult::Ok(serialize(signal_format, debug, &signal))
     });
 
-    Ok(Response::new(Body::from_stream(stream)))
+    let mut response = Response::new(Body::from_stream(stream));
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(signal_format.mime()));
+    Ok(response)
+}
+
+fn too_many_requests(retry_after: RetryAfter) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, retry_after.0.to_string())],
+        "Too many submissions are queued, please retry later",
+    )
+        .into_response()
+}
+
+async fn handle_dry_run_request(request: Request, max_body_size_bytes: u64) -> Response {
+    match handle_dry_run_request_inner(request, max_body_size_bytes).await {
+        Ok(response) => response,
+        Err(err) => {
+            error!("Error handling the dry run request: {:#}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+    }
+}
+
+async fn handle_dry_run_request_inner(
+    request: Request,
+    max_body_size_bytes: u64,
+) -> Result<Response> {
+    let (content_type, report_format) = negotiate_formats(&request);
+    let config = read_body(request, max_body_size_bytes).await?;
+    let format = DataFormat::from_mime(&content_type).unwrap_or_default();
+
+    let report = composer::dry_run_submission(&config, format);
+    let status = match report {
+        DryRunReport::Resolved { .. } => StatusCode::OK,
+        DryRunReport::Invalid { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    let body = report_format.serialize(&report)?;
+    Ok((status, [(CONTENT_TYPE, report_format.mime())], body).into_response())
+}
+
+/// The format of the request body, and the format of the response which is JSON
+/// unless MessagePack is accepted
+fn negotiate_formats(request: &Request) -> (String, DataFormat) {
+    let header = |name| request.headers().get(name).and_then(|value| value.to_str().ok());
+    let response_format = match header(ACCEPT).and_then(DataFormat::from_accept) {
+        Some(DataFormat::MessagePack) => DataFormat::MessagePack,
+        _ => DataFormat::Json,
+    };
+    (header(CONTENT_TYPE).unwrap_or_default().to_owned(), response_format)
+}
+
+async fn read_body(request: Request, max_body_size_bytes: u64) -> Result<Vec<u8>> {
+    let body_size = request.body().size_hint().upper().unwrap_or(max_body_size_bytes + 1);
+    if body_size > max_body_size_bytes {
+        bail!("The size of the request body exceeds the limit: {}", body_size);
+    }
+
+    Ok(to_bytes(request.into_body(), usize::MAX).await?.to_vec())
+}
+
+/// Read the submission from the first part, and stream the following parts into
+/// the attachments directory. The attachments are keyed by their part names.
+async fn read_multipart(
+    mut multipart: Multipart,
+    limits: BodyLimits,
+) -> Result<(Vec<u8>, DataFormat, HashMap<String, PathBuf>)> {
+    let mut attachments = HashMap::new();
+    let result = async {
+        let mut config = vec![];
+        let format = {
+            let Some(mut field) = multipart.next_field().await? else {
+                bail!("The request contains no submission");
+            };
+
+            let format = field.content_type().and_then(DataFormat::from_mime).unwrap_or_default();
+            while let Some(chunk) = field.chunk().await? {
+                config.extend_from_slice(&chunk);
+                if config.len() as u64 > limits.max_body_size_bytes {
+                    bail!(
+                        "The size of the submission exceeds the limit: {}",
+                        limits.max_body_size_bytes
+                    );
+                }
+            }
+            format
+        };
+
+        let mut total_size = 0;
+        while let Some(mut field) = multipart.next_field().await? {
+            let Some(name) = field.name().map(str::to_owned) else {
+                bail!("The attachment has no part name");
+            };
+            if attachments.contains_key(&name) {
+                bail!("Duplicate attachment: {name}");
+            }
+
+            let path = conf::PATHS.attachments.join(nano_id::base62::<16>());
+            let mut file = File::create(&path).await.context("Error creating the attachment")?;
+            attachments.insert(name, path);
+
+            while let Some(chunk) = field.chunk().await? {
+                total_size += chunk.len() as u64;
+                if total_size > limits.max_attachments_size_bytes {
+                    bail!(
+                        "The size of the attachments exceeds the limit: {}",
+                        limits.max_attachments_size_bytes
+                    );
+                }
+
+                file.write_all(&chunk).await.context("Error writing the attachment")?;
+            }
+            file.flush().await.context("Error writing the attachment")?;
+        }
+
+        Ok((config, format))
+    }
+    .await;
+
+    match result {
+        Ok((config, format)) => Ok((config, format, attachments)),
+        Err(err) => {
+            for path in attachments.values() {
+                _ = fs::remove_file(path).await;
+            }
+            Err(err)
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use seele_composer::{self as composer, ComposerQueueItem};
 use seele_config::{self as conf, ExchangeConfig};
+use seele_shared as shared;
 use tokio::sync::mpsc;
 use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
 use tracing::info;
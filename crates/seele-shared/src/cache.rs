@@ -0,0 +1,100 @@
+use std::sync::{
+    Arc, LazyLock, RwLock,
+    atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+
+/// A snapshot of the statistics of a managed cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub entry_count: u64,
+    pub size_bytes: u64,
+    pub hit_count: u64,
+    pub miss_count: u64,
+    pub hit_ratio: f64,
+}
+
+/// A cache that can be observed via metrics and managed via the admin endpoint
+pub trait ManagedCache: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn stats(&self) -> CacheStats;
+
+    /// Keys of the entries in the cache, rendered as strings
+    fn keys(&self) -> Vec<String>;
+
+    /// Invalidate the entries whose keys start with the prefix, or all entries
+    /// if no prefix is provided. Returns the count of the matched entries.
+    fn purge(&self, prefix: Option<&str>) -> u64;
+}
+
+#[derive(Debug, Default)]
+pub struct CacheCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounter {
+    pub const fn new() -> Self {
+        Self { hits: AtomicU64::new(0), misses: AtomicU64::new(0) }
+    }
+
+    #[inline]
+    pub fn record(&self, hit: bool) {
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn make_stats(&self, name: &'static str, entry_count: u64, size_bytes: u64) -> CacheStats {
+        let hit_count = self.hits.load(Ordering::Relaxed);
+        let miss_count = self.misses.load(Ordering::Relaxed);
+        let hit_ratio = match hit_count + miss_count {
+            0 => 0.0,
+            total => hit_count as f64 / total as f64,
+        };
+
+        CacheStats { name, entry_count, size_bytes, hit_count, miss_count, hit_ratio }
+    }
+}
+
+static CACHES: LazyLock<RwLock<Vec<Arc<dyn ManagedCache>>>> = LazyLock::new(Default::default);
+
+/// Register a cache, replacing the existing one with the same name
+pub fn register(cache: Arc<dyn ManagedCache>) {
+    let mut caches = CACHES.write().unwrap();
+    caches.retain(|item| item.name() != cache.name());
+    caches.push(cache);
+}
+
+pub fn caches() -> Vec<Arc<dyn ManagedCache>> {
+    CACHES.read().unwrap().clone()
+}
+
+pub fn find(name: &str) -> Option<Arc<dyn ManagedCache>> {
+    CACHES.read().unwrap().iter().find(|cache| cache.name() == name).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CacheCounter;
+
+    #[test]
+    fn test_cache_counter() {
+        let counter = CacheCounter::new();
+        assert_eq!(counter.make_stats("test", 0, 0).hit_ratio, 0.0);
+
+        counter.record(true);
+        counter.record(true);
+        counter.record(true);
+        counter.record(false);
+
+        let stats = counter.make_stats("test", 3, 114);
+        assert_eq!((stats.hit_count, stats.miss_count), (3, 1));
+        assert_eq!(stats.hit_ratio, 0.75);
+    }
+}
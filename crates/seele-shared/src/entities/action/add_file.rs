@@ -1,27 +1,114 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{collections::HashMap, fmt::Display, path::PathBuf};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+use crate::path::SafePath;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct Config {
     pub files: Vec<FileItem>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct FileItem {
-    pub path: PathBuf,
+    pub path: SafePath,
+
+    /// When presents, the file is treated as an archive and unpacked into the
+    /// directory specified by `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<ArchiveFormat>,
+
+    /// Expected sha256 hash of the file in hex, checked after the file is
+    /// written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+
+    /// Expected size of the file in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// When presents, the file is materialized once in the data store of the
+    /// worker and hard-linked into the submission
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<StoreKey>,
 
     #[serde(flatten)]
     pub ext: FileItemExt,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, Serialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    Zip,
+
+    #[serde(rename = "tar")]
+    Tar,
+
+    #[serde(rename = "tar.gz", alias = "tgz")]
+    TarGz,
+
+    #[serde(rename = "tar.zst")]
+    TarZst,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(untagged)]
+pub enum StoreKey {
+    /// Keyed by the `sha256` of the file when `true`
+    ContentHash(bool),
+
+    /// Keyed by the name, e.g. the id and the version of the problem
+    Named(String),
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum FileItemExt {
-    Http { url: String },
-    PlainText { plain: String },
-    Base64 { base64: String },
-    LocalPath { local: PathBuf },
+    Http {
+        url: String,
+    },
+    S3 {
+        s3: S3Object,
+    },
+    PlainText {
+        plain: String,
+    },
+    Base64 {
+        base64: String,
+    },
+    LocalPath {
+        local: PathBuf,
+    },
+    Template {
+        template: String,
+        #[serde(default)]
+        vars: HashMap<String, String>,
+    },
+    Attachment {
+        attachment: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct S3Object {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Zip => "zip",
+                Self::Tar => "tar",
+                Self::TarGz => "tar.gz",
+                Self::TarZst => "tar.zst",
+            }
+        )
+    }
 }
 
 impl Display for FileItem {
@@ -34,11 +121,15 @@ impl Display for FileItem {
             self.path.display(),
             match &self.ext {
                 FileItemExt::Http { url } => url.to_string(),
+                FileItemExt::S3 { s3 } => format!("s3://{}/{}", s3.bucket, s3.key),
                 FileItemExt::PlainText { plain } =>
                     format!("{}...", plain.as_str().truncate_ellipse(30)),
                 FileItemExt::Base64 { base64 } =>
                     format!("{}...", base64.as_str().truncate_ellipse(30)),
                 FileItemExt::LocalPath { local } => format!("{}", local.display()),
+                FileItemExt::Template { template, .. } =>
+                    format!("{}...", template.as_str().truncate_ellipse(30)),
+                FileItemExt::Attachment { attachment } => format!("attachment:{attachment}"),
             }
         )
     }
@@ -1,10 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub mod add_file;
 pub mod noop;
 pub mod run_container;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(tag = "action")]
 pub enum ActionTaskConfig {
     #[serde(rename = "seele/noop@1")]
@@ -35,7 +36,7 @@ pub enum ActionSuccessReportExt {
     Noop(noop::ExecutionReport),
     AddFile,
     RunCompile(run_container::run_judge::compile::ExecutionReport),
-    RunContainer(run_container::ExecutionReport),
+    RunContainer(Box<run_container::ExecutionReport>),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -43,5 +44,5 @@ pub enum ActionSuccessReportExt {
 pub enum ActionFailureReportExt {
     Noop(noop::ExecutionReport),
     AddFile(add_file::FailedReport),
-    RunContainer(run_container::ExecutionReport),
+    RunContainer(Box<run_container::ExecutionReport>),
 }
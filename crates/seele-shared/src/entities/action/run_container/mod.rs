@@ -4,19 +4,23 @@ use std::{
 };
 
 use anyhow::{Result, bail};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub mod run_judge;
 pub mod runj;
 
 use runj::RlimitItem;
-use seele_config::OciImage;
+use seele_config::{NetworkMode, OciImage};
+
+use crate::path::SafePath;
 
 pub type ExecutionReport = runj::ContainerExecutionReport;
 pub type ExecutionStatus = runj::ContainerExecutionStatus;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct Config {
+    #[schemars(with = "String")]
     pub image: OciImage,
 
     #[serde(default = "default_cwd")]
@@ -33,8 +37,17 @@ pub struct Config {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub mounts: Vec<MountConfig>,
 
+    #[serde(default)]
+    pub network: NetworkMode,
+
     #[serde(default)]
     pub limits: LimitsConfig,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub capture: Option<CaptureConfig>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sampling: Option<SamplingConfig>,
 }
 
 #[inline]
@@ -42,7 +55,7 @@ fn default_cwd() -> PathBuf {
     "/".into()
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum CommandConfig {
     Simple(String),
@@ -69,7 +82,7 @@ impl TryInto<Vec<String>> for CommandConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum MountConfig {
     Simple(String),
@@ -77,35 +90,43 @@ pub enum MountConfig {
 }
 
 impl MountConfig {
-    pub fn into_runj_mount(self, parent_path_absolute: &Path) -> Result<runj::MountConfig> {
+    pub async fn into_runj_mount(self, parent_path_absolute: &Path) -> Result<runj::MountConfig> {
+        let resolve = |from: &str| {
+            let from = SafePath::try_from(from);
+            async move { from?.resolve(parent_path_absolute).await }
+        };
+
         Ok(match self {
             Self::Simple(config) => {
                 let parts: Vec<_> = config.split(':').collect();
                 match parts[..] {
                     [item] => runj::MountConfig {
-                        from: parent_path_absolute.join(item),
+                        from: resolve(item).await?,
                         to: ["/", item].iter().collect(),
                         options: None,
                     },
                     [from, to] => runj::MountConfig {
-                        from: parent_path_absolute.join(from),
+                        from: resolve(from).await?,
                         to: ["/", to].iter().collect(),
                         options: None,
                     },
                     [from, to, options] => runj::MountConfig {
-                        from: parent_path_absolute.join(from),
+                        from: resolve(from).await?,
                         to: ["/", to].iter().collect(),
                         options: Some(options.split(',').map(|s| s.to_string()).collect()),
                     },
                     _ => bail!("Unknown mount value: {}", config),
                 }
             }
-            Self::Full(config) => config,
+            Self::Full(config) => runj::MountConfig {
+                from: SafePath::try_from(config.from)?.resolve(parent_path_absolute).await?,
+                ..config
+            },
         })
     }
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize)]
 pub struct LimitsConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_ms: Option<u64>,
@@ -118,6 +139,20 @@ pub struct LimitsConfig {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fsize_kib: Option<u64>,
+
+    #[serde(default)]
+    pub output_limit_kib: OutputLimitConfig,
+}
+
+/// Limits of the output of each stream enforced by the worker, which apply no
+/// matter where the stream is redirected to
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize)]
+pub struct OutputLimitConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<u64>,
 }
 
 impl From<LimitsConfig> for runj::LimitsConfig {
@@ -149,3 +184,73 @@ impl From<LimitsConfig> for runj::LimitsConfig {
         }
     }
 }
+
+/// Captures the first and the last part of the output into the report, from
+/// the redirected file or directly from the container if not redirected
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct CaptureConfig {
+    #[serde(default)]
+    pub stdout: bool,
+
+    #[serde(default)]
+    pub stderr: bool,
+
+    #[serde(default = "default_capture_kib")]
+    pub head_kib: u64,
+
+    #[serde(default = "default_capture_kib")]
+    pub tail_kib: u64,
+}
+
+#[inline]
+fn default_capture_kib() -> u64 {
+    4
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CapturedOutput {
+    /// The whole output if not truncated, otherwise its first part
+    pub head: String,
+
+    /// The last part of the output, only present if truncated
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tail: Option<String>,
+
+    pub truncated: bool,
+
+    pub size_bytes: u64,
+}
+
+/// Samples the resource usage of the container periodically into the report.
+/// The interval is doubled and every other sample is dropped once the samples
+/// reach the maximum count.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct SamplingConfig {
+    #[serde(default = "default_sampling_interval_ms")]
+    pub interval_ms: u64,
+
+    #[serde(default = "default_sampling_max_samples")]
+    pub max_samples: usize,
+}
+
+#[inline]
+fn default_sampling_interval_ms() -> u64 {
+    100
+}
+
+#[inline]
+fn default_sampling_max_samples() -> usize {
+    256
+}
+
+/// The samples of the resource usage, stored as columns
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ResourceTimeline {
+    /// The time elapsed since the sampling started
+    pub time_ms: Vec<u64>,
+
+    pub memory_kib: Vec<u64>,
+
+    /// The total CPU time consumed so far
+    pub cpu_time_ms: Vec<u64>,
+}
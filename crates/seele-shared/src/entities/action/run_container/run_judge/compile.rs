@@ -1,9 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::MountFile;
-use crate::entities::run_container;
+use crate::{entities::run_container, path::SafePath};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct Config {
     #[serde(flatten)]
     pub run_container_config: run_container::Config,
@@ -12,17 +13,21 @@ pub struct Config {
     pub sources: Vec<MountFile>,
 
     #[serde(default)]
-    pub saves: Vec<String>,
+    pub saves: Vec<SafePath>,
 
+    // The defaults depend on the worker configuration, so they are kept out of
+    // the schema
     #[serde(default)]
+    #[schemars(skip_serializing)]
     pub cache: CacheConfig,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct CacheConfig {
     pub enabled: bool,
 
     #[serde(default = "default_max_allowed_size_mib")]
+    #[schemars(skip_serializing)]
     pub max_allowed_size_mib: u64,
 
     #[serde(default)]
@@ -48,5 +53,5 @@ fn default_max_allowed_size_mib() -> u64 {
 #[serde(untagged)]
 pub enum ExecutionReport {
     CacheHit { cache_hit: bool },
-    CacheMiss(run_container::ExecutionReport),
+    CacheMiss(Box<run_container::ExecutionReport>),
 }
@@ -1,14 +1,17 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 use anyhow::bail;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::{Deserialize, Serialize, de};
 
+use crate::path::SafePath;
+
 pub mod compile;
 pub mod run;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct MountFile {
-    pub from_path: String,
+    pub from_path: SafePath,
     pub to_path: String,
     pub exec: bool,
 }
@@ -32,22 +35,35 @@ impl Serialize for MountFile {
     }
 }
 
+impl JsonSchema for MountFile {
+    fn schema_name() -> Cow<'static, str> {
+        "MountFile".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "`<from_path>[:<to_path>][:exec]`, e.g. `main.c` or `main:/bin/main:exec`"
+        })
+    }
+}
+
 impl TryFrom<&str> for MountFile {
     type Error = anyhow::Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         Ok(match value.split(':').collect::<Vec<_>>()[..] {
             [from_path] => {
-                Self { from_path: from_path.into(), to_path: from_path.into(), exec: false }
+                Self { from_path: from_path.try_into()?, to_path: from_path.into(), exec: false }
             }
             [from_path, "exec"] => {
-                Self { from_path: from_path.into(), to_path: from_path.into(), exec: true }
+                Self { from_path: from_path.try_into()?, to_path: from_path.into(), exec: true }
             }
             [from_path, to_path] => {
-                Self { from_path: from_path.into(), to_path: to_path.into(), exec: false }
+                Self { from_path: from_path.try_into()?, to_path: to_path.into(), exec: false }
             }
             [from_path, to_path, "exec"] => {
-                Self { from_path: from_path.into(), to_path: to_path.into(), exec: true }
+                Self { from_path: from_path.try_into()?, to_path: to_path.into(), exec: true }
             }
             _ => bail!("Unexpected file item: {value}"),
         })
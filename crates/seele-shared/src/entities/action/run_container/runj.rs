@@ -1,5 +1,7 @@
 use std::{fmt::Display, path::PathBuf};
 
+use schemars::JsonSchema;
+use seele_config::NetworkMode;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -24,6 +26,8 @@ pub struct RunjConfig {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub mounts: Vec<MountConfig>,
 
+    pub network: NetworkMode,
+
     pub limits: LimitsConfig,
 }
 
@@ -46,7 +50,7 @@ pub struct OverlayfsConfig {
     pub merged_dir: PathBuf,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize)]
 pub struct FdConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stdin: Option<PathBuf>,
@@ -64,8 +68,10 @@ pub struct FdConfig {
     pub stderr_to_stdout: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct MountConfig {
+    /// Relative to the submission root in the submissions, like the simple
+    /// form, while the ones added by the worker are absolute
     pub from: PathBuf,
     pub to: PathBuf,
 
@@ -133,6 +139,44 @@ pub struct ContainerExecutionReport {
     pub cpu_user_time_ms: u64,
     pub cpu_kernel_time_ms: u64,
     pub memory_usage_kib: u64,
+
+    // The statistics below are absent if the kernel does not provide them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oom_count: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oom_kill_count: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_peak: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_read_bytes: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_write_bytes: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_pressure_some_us: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_pressure_full_us: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voluntary_context_switches: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub involuntary_context_switches: Option<u64>,
+
+    // The outputs below are captured by the worker instead of runj
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<super::CapturedOutput>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<super::CapturedOutput>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<super::ResourceTimeline>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -1,7 +1,31 @@
 pub use action::*;
 pub use report::*;
+use schemars::{Schema, SchemaGenerator};
 pub use submission::*;
 
 mod action;
 mod report;
 mod submission;
+
+/// The JSON Schema of the submissions, published in
+/// `docs/public/schema/submission.json`
+pub fn submission_schema() -> Schema {
+    SchemaGenerator::default().into_root_schema_for::<SubmissionConfig>()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    #[test]
+    fn test_submission_schema() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../docs/public/schema/submission.json");
+        let schema = serde_json::to_string_pretty(&super::submission_schema()).unwrap() + "\n";
+        assert!(
+            fs::read_to_string(&path).is_ok_and(|published| published == schema),
+            "The published schema is outdated, run `seele schema > \
+             docs/public/schema/submission.json` to update it"
+        );
+    }
+}
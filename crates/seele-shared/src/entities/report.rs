@@ -1,12 +1,13 @@
-use std::path::PathBuf;
-
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::path::SafePath;
+
 pub type SubmissionReport = IndexMap<String, serde_yaml::Value>;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum SubmissionReporter {
     JavaScript { javascript: String },
@@ -24,10 +25,10 @@ pub struct SubmissionReportConfig {
     pub uploads: Vec<SubmissionReportUploadConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct SubmissionReportEmbedConfig {
-    pub path: PathBuf,
+    pub path: SafePath,
     pub field: String,
     pub truncate_kib: usize,
 
@@ -35,10 +36,10 @@ pub struct SubmissionReportEmbedConfig {
     pub ignore_if_missing: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct SubmissionReportUploadConfig {
-    pub path: PathBuf,
+    pub path: SafePath,
     pub target: Url,
 
     #[serde(default = "default_upload_method")]
@@ -51,7 +52,7 @@ pub struct SubmissionReportUploadConfig {
     pub ignore_if_missing: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SubmissionReportUploadMethod {
     Post,
@@ -7,6 +7,7 @@ use std::{
 use chrono::{DateTime, Utc};
 use either::Either;
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::*;
@@ -15,7 +16,7 @@ pub type UtcTimestamp = DateTime<Utc>;
 
 pub type SequenceTasks = IndexMap<String, Arc<TaskConfig>>;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum ParallelTasks {
     Anonymous(Vec<Arc<TaskConfig>>),
@@ -36,18 +37,28 @@ impl<'a> ParallelTasks {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct SubmissionConfig {
     #[serde(skip_serializing, skip_deserializing, default = "make_submitted_at")]
     pub submitted_at: UtcTimestamp,
 
+    // Keeps the random default out of the schema
     #[serde(default = "random_submission_id")]
+    #[schemars(skip_serializing)]
     pub id: String,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tracing_attribute: Option<String>,
 
+    /// Overrides the priority provided by the exchange
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<SubmissionPriority>,
+
+    /// The tenant sharing the runners with the others fairly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+
     #[serde(rename = "steps")]
     pub tasks: SequenceTasks,
 
@@ -55,6 +66,40 @@ pub struct SubmissionConfig {
     pub reporter: Option<SubmissionReporter>,
 }
 
+/// The actions of the submissions with higher priorities take the runners first
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    JsonSchema,
+    Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl SubmissionPriority {
+    /// Map the priority of the AMQP messages, which usually ranges from 0 to 9
+    pub fn from_amqp(priority: u8) -> Self {
+        match priority {
+            0..=3 => Self::Low,
+            4..=6 => Self::Normal,
+            _ => Self::High,
+        }
+    }
+}
+
 #[inline]
 fn make_submitted_at() -> UtcTimestamp {
     Utc::now()
@@ -73,7 +118,7 @@ pub struct Submission {
     pub root_node: Arc<RootTaskNode>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct TaskConfig {
     #[serde(default, skip_serializing)]
     pub progress: bool,
@@ -85,9 +130,11 @@ pub struct TaskConfig {
     pub tags: Option<HashMap<String, String>>,
 
     #[serde(default, flatten, skip_deserializing)]
+    #[schemars(skip)]
     pub status: RwLock<TaskStatus>,
 
     #[serde(default, skip_deserializing)]
+    #[schemars(skip)]
     pub embeds: RwLock<TaskEmbeds>,
 
     #[serde(default, skip_serializing)]
@@ -100,7 +147,7 @@ pub struct TaskConfig {
     pub ext: TaskConfigExt,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct TaskReportConfig {
     #[serde(default)]
     pub embeds: Vec<TaskReportEmbedConfig>,
@@ -109,7 +156,7 @@ pub struct TaskReportConfig {
     pub uploads: Vec<TaskReportUploadConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct TaskReportEmbedConfig {
     #[serde(default)]
     pub when: TaskReportWhenConfig,
@@ -118,7 +165,7 @@ pub struct TaskReportEmbedConfig {
     pub inner: SubmissionReportEmbedConfig,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct TaskReportUploadConfig {
     #[serde(default)]
     pub when: TaskReportWhenConfig,
@@ -127,20 +174,15 @@ pub struct TaskReportUploadConfig {
     pub inner: SubmissionReportUploadConfig,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskReportWhenConfig {
     Success,
     Failure,
+    #[default]
     Always,
 }
 
-impl Default for TaskReportWhenConfig {
-    fn default() -> Self {
-        Self::Always
-    }
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum TaskEmbeds {
@@ -154,7 +196,7 @@ impl Default for TaskEmbeds {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 #[allow(clippy::large_enum_variant)]
 pub enum TaskConfigExt {
@@ -170,32 +212,31 @@ impl TaskConfigExt {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct SequenceTaskConfig {
     #[serde(rename = "steps")]
     pub tasks: SequenceTasks,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct ParallelTaskConfig {
     #[serde(rename = "parallel")]
     pub tasks: ParallelTasks,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Default, Clone, Serialize)]
 #[serde(tag = "status", rename_all = "UPPERCASE")]
 pub enum TaskStatus {
+    #[default]
     Pending,
     Skipped,
     Running,
-    Failed { report: TaskFailedReport },
-    Success { report: TaskSuccessReport },
-}
-
-impl Default for TaskStatus {
-    fn default() -> Self {
-        Self::Pending
-    }
+    Failed {
+        report: TaskFailedReport,
+    },
+    Success {
+        report: TaskSuccessReport,
+    },
 }
 
 #[derive(Debug, Clone, Serialize)]
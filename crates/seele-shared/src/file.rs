@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{io::ErrorKind, path::Path};
 
 use anyhow::{Context, Result};
 use tokio::fs;
@@ -13,5 +13,17 @@ pub async fn create_parent_directories(path: &Path) -> Result<()> {
 
 pub async fn create_file(path: &Path) -> Result<fs::File> {
     create_parent_directories(path).await?;
+    remove_existing_file(path).await?;
     fs::File::create(path).await.context("Error creating the file")
 }
+
+/// Remove the file about to be written instead of truncating it, since it may
+/// be a hard link to a data store entry shared by the other submissions
+pub async fn remove_existing_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            Err(err).context("Error removing the existing file")
+        }
+        _ => Ok(()),
+    }
+}
@@ -7,11 +7,13 @@ use tokio::{
     io::{AsyncReadExt, AsyncSeekExt, BufReader},
 };
 
+pub mod cache;
 pub mod cond;
 pub mod entities;
 pub mod file;
 pub mod http;
 pub mod metrics;
+pub mod path;
 pub mod runner;
 
 pub static TINI_PRESENTS: LazyLock<bool> = LazyLock::new(|| env::var_os("TINI_VERSION").is_some());
@@ -9,7 +9,7 @@ use opentelemetry::{
 };
 use opentelemetry_sdk::{Resource, metrics::SdkMeterProvider};
 
-use super::runner;
+use super::{cache, runner};
 use crate::conf;
 
 static METRICS_RESOURCE: LazyLock<Resource> = LazyLock::new(|| {
@@ -51,6 +51,9 @@ pub fn metrics_resource() -> Resource {
     METRICS_RESOURCE.clone()
 }
 
+const CACHE_NAME: &str = "seele.cache.name";
+const TENANT_NAME: &str = "seele.tenant.name";
+
 static METER_PROVIDER: OnceLock<SdkMeterProvider> = OnceLock::new();
 
 static SEELE_METER: LazyLock<Meter> = LazyLock::new(|| {
@@ -108,6 +111,83 @@ fn register_observable_gauges() {
             observer.observe(runner::PENDING_TASKS.load(Ordering::SeqCst), &[])
         })
         .build();
+
+    SEELE_METER
+        .u64_observable_gauge("seele.runner.tenant.pending.count")
+        .with_description("Count of actions waiting for the runners of each tenant")
+        .with_callback(|observer| {
+            for stats in runner::tenant_stats() {
+                observer.observe(
+                    stats.pending_count,
+                    &[KeyValue::new(TENANT_NAME, stats.tenant.to_string())],
+                );
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .u64_observable_gauge("seele.runner.tenant.running.count")
+        .with_description("Count of runners used by each tenant")
+        .with_callback(|observer| {
+            for stats in runner::tenant_stats() {
+                observer.observe(
+                    stats.running_count,
+                    &[KeyValue::new(TENANT_NAME, stats.tenant.to_string())],
+                );
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .u64_observable_gauge("seele.cache.entry.count")
+        .with_description("Count of entries in the cache")
+        .with_callback(|observer| {
+            for stats in cache::caches().iter().map(|cache| cache.stats()) {
+                observer.observe(stats.entry_count, &[KeyValue::new(CACHE_NAME, stats.name)]);
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .u64_observable_gauge("seele.cache.size")
+        .with_description("Weighted size of the entries in the cache")
+        .with_unit("By")
+        .with_callback(|observer| {
+            for stats in cache::caches().iter().map(|cache| cache.stats()) {
+                observer.observe(stats.size_bytes, &[KeyValue::new(CACHE_NAME, stats.name)]);
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .u64_observable_counter("seele.cache.hit.count")
+        .with_description("Count of cache hits")
+        .with_callback(|observer| {
+            for stats in cache::caches().iter().map(|cache| cache.stats()) {
+                observer.observe(stats.hit_count, &[KeyValue::new(CACHE_NAME, stats.name)]);
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .u64_observable_counter("seele.cache.miss.count")
+        .with_description("Count of cache misses")
+        .with_callback(|observer| {
+            for stats in cache::caches().iter().map(|cache| cache.stats()) {
+                observer.observe(stats.miss_count, &[KeyValue::new(CACHE_NAME, stats.name)]);
+            }
+        })
+        .build();
+
+    SEELE_METER
+        .f64_observable_gauge("seele.cache.hit_ratio")
+        .with_description("Ratio of cache hits to all cache lookups")
+        .with_callback(|observer| {
+            for stats in cache::caches().iter().map(|cache| cache.stats()) {
+                observer.observe(stats.hit_ratio, &[KeyValue::new(CACHE_NAME, stats.name)]);
+            }
+        })
+        .build();
 }
 
 static SUBMISSION_HANDLING_HISTOGRAM: LazyLock<Histogram<f64>> = LazyLock::new(|| {
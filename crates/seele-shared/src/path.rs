@@ -0,0 +1,173 @@
+use std::{
+    fmt::Display,
+    io::ErrorKind,
+    ops::Deref,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de};
+use tokio::fs;
+
+/// A relative path that stays inside the directory it is joined onto. Absolute
+/// paths and `..` components are rejected when it is constructed, and symbolic
+/// links escaping the directory are rejected by [`SafePath::resolve`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct SafePath(PathBuf);
+
+impl SafePath {
+    /// Join the path onto the root directory, making sure that the existing
+    /// part of the result does not leave the root directory via symbolic
+    /// links
+    pub async fn resolve(&self, root: &Path) -> Result<PathBuf> {
+        let path = root.join(&self.0);
+        let root = fs::canonicalize(root)
+            .await
+            .with_context(|| format!("Error resolving the root directory: {}", root.display()))?;
+
+        let mut current = path.as_path();
+        loop {
+            match fs::canonicalize(current).await {
+                Ok(real_path) => {
+                    if !real_path.starts_with(&root) {
+                        bail!("The path escapes the root directory: {}", self.0.display());
+                    }
+                    break;
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    // A dangling symbolic link could still point to the outside
+                    if fs::symlink_metadata(current).await.is_ok() {
+                        bail!("The path contains a dangling symbolic link: {}", self.0.display());
+                    }
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Error resolving the path: {}", self.0.display())
+                    });
+                }
+            }
+
+            match current.parent() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+impl TryFrom<PathBuf> for SafePath {
+    type Error = anyhow::Error;
+
+    fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
+        if value.as_os_str().is_empty() {
+            bail!("The path is empty");
+        }
+
+        for component in value.components() {
+            match component {
+                Component::Normal(_) | Component::CurDir => {}
+                Component::ParentDir => bail!("The path contains `..`: {}", value.display()),
+                Component::RootDir | Component::Prefix(_) => {
+                    bail!("The path is absolute: {}", value.display())
+                }
+            }
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl TryFrom<&str> for SafePath {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        PathBuf::from(value).try_into()
+    }
+}
+
+impl<'de> Deserialize<'de> for SafePath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let path = PathBuf::deserialize(deserializer)?;
+        path.try_into().map_err(|err| de::Error::custom(format!("{err:#}")))
+    }
+}
+
+impl Deref for SafePath {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for SafePath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Display for SafePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use tokio::fs;
+
+    use super::SafePath;
+
+    #[test]
+    fn test_safe_path_parse() {
+        for path in ["main.c", "./data/1.in", "a/./b"] {
+            assert!(SafePath::try_from(path).is_ok(), "{path}");
+        }
+
+        for path in ["", "/etc/passwd", "../evil", "data/../../evil", "a/.."] {
+            assert!(SafePath::try_from(path).is_err(), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_safe_path_resolve() {
+        const ROOT: &str = "./test-safe-path";
+
+        fs::create_dir_all(format!("{ROOT}/root/data")).await.unwrap();
+        symlink("/tmp", format!("{ROOT}/root/outside")).unwrap();
+        symlink("data", format!("{ROOT}/root/inside")).unwrap();
+        symlink("/nonexistent-seele", format!("{ROOT}/root/dangling")).unwrap();
+
+        let root = std::path::Path::new(ROOT).join("root");
+        let resolve = |path: &'static str| {
+            let root = root.clone();
+            async move { SafePath::try_from(path).unwrap().resolve(&root).await }
+        };
+
+        let results = (
+            resolve("data/1.in").await,
+            resolve("new/dir/file").await,
+            resolve("inside/1.in").await,
+            resolve("outside/evil").await,
+            resolve("outside").await,
+            resolve("dangling").await,
+        );
+        fs::remove_dir_all(ROOT).await.unwrap();
+
+        assert_eq!(results.0.unwrap(), root.join("data/1.in"));
+        assert_eq!(results.1.unwrap(), root.join("new/dir/file"));
+        assert!(results.2.is_ok());
+        assert!(results.3.is_err());
+        assert!(results.4.is_err());
+        assert!(results.5.is_err());
+    }
+}
@@ -1,28 +1,414 @@
-use std::sync::{
-    LazyLock,
-    atomic::{AtomicU64, Ordering},
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
+use indexmap::IndexMap;
 use tokio::{
-    sync::Semaphore,
+    sync::oneshot,
     task::{self, JoinError},
 };
 
-use crate::conf;
+use crate::{
+    conf::{self, DEFAULT_TENANT, TenantConfig},
+    entities::SubmissionPriority,
+};
 
 pub static PENDING_TASKS: LazyLock<AtomicU64> = LazyLock::new(|| AtomicU64::new(0));
 
-static RUNNERS: LazyLock<Semaphore> =
-    LazyLock::new(|| Semaphore::new(conf::CONFIG.thread_counts.runner));
+static RUNNERS: LazyLock<RunnerPool> =
+    LazyLock::new(|| RunnerPool::new(conf::CONFIG.thread_counts.runner, &conf::CONFIG.tenants));
+
+/// The virtual time a tenant of weight 1 spends for a runner
+const STRIDE: u64 = 1 << 20;
+
+tokio::task_local! {
+    /// How the actions executed by the current task are scheduled
+    pub static SCHEDULING: Scheduling;
+}
+
+#[derive(Debug, Clone)]
+pub struct Scheduling {
+    pub priority: SubmissionPriority,
+    pub tenant: Arc<str>,
+}
+
+impl Default for Scheduling {
+    fn default() -> Self {
+        Self { priority: Default::default(), tenant: DEFAULT_TENANT.into() }
+    }
+}
 
 pub async fn spawn_blocking<F, R>(f: F) -> Result<R, JoinError>
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
+    let scheduling = SCHEDULING.try_with(Clone::clone).unwrap_or_default();
+
     PENDING_TASKS.fetch_add(1, Ordering::SeqCst);
-    let _permit = RUNNERS.acquire().await.unwrap();
+    let _permit = RUNNERS.acquire(&scheduling).await;
     PENDING_TASKS.fetch_sub(1, Ordering::SeqCst);
 
     task::spawn_blocking(f).await
 }
+
+/// Counts of the waiting and running actions of each tenant
+pub fn tenant_stats() -> Vec<TenantStats> {
+    RUNNERS.tenant_stats()
+}
+
+#[derive(Debug, Clone)]
+pub struct TenantStats {
+    pub tenant: Arc<str>,
+    pub pending_count: u64,
+    pub running_count: u64,
+}
+
+/// A semaphore handing the released runners to the tenants by weighted fair
+/// queuing within their caps, so that the priorities chosen by a tenant cannot
+/// take the shares of the others. The waiters of a tenant are served by their
+/// priorities, and then in their order of arrival.
+pub struct RunnerPool {
+    configs: HashMap<String, TenantConfig>,
+    state: Mutex<RunnerPoolState>,
+}
+
+struct RunnerPoolState {
+    available: usize,
+    next_sequence: u64,
+
+    /// The virtual time of the last served tenant, from which the newly active
+    /// tenants start, so that the idle ones do not bank their shares
+    virtual_time: u64,
+
+    tenants: HashMap<Arc<str>, TenantState>,
+}
+
+struct TenantState {
+    stride: u64,
+    max_runners: usize,
+    running: usize,
+    virtual_time: u64,
+    waiters: BinaryHeap<Waiter>,
+}
+
+struct Waiter {
+    priority: SubmissionPriority,
+    sequence: Reverse<u64>,
+    tx: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        (self.priority, self.sequence) == (other.priority, other.sequence)
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.priority, self.sequence).cmp(&(other.priority, other.sequence))
+    }
+}
+
+pub struct RunnerPermit<'a> {
+    pool: &'a RunnerPool,
+    tenant: Arc<str>,
+}
+
+impl Drop for RunnerPermit<'_> {
+    fn drop(&mut self) {
+        self.pool.release(&self.tenant);
+    }
+}
+
+/// Gives the runner back if the waiter is cancelled after being handed one
+struct PendingPermit<'a> {
+    pool: &'a RunnerPool,
+    tenant: Arc<str>,
+    rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for PendingPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.pool.release(&self.tenant);
+            } else {
+                self.pool.remove_idle_tenant(&self.tenant);
+            }
+        }
+    }
+}
+
+impl RunnerPool {
+    pub fn new(count: usize, configs: &IndexMap<String, TenantConfig>) -> Self {
+        Self {
+            configs: configs.iter().map(|(name, config)| (name.clone(), config.clone())).collect(),
+            state: Mutex::new(RunnerPoolState {
+                available: count,
+                next_sequence: 0,
+                virtual_time: 0,
+                tenants: HashMap::new(),
+            }),
+        }
+    }
+
+    pub async fn acquire(&self, scheduling: &Scheduling) -> RunnerPermit<'_> {
+        let tenant = scheduling.tenant.clone();
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            let virtual_time = state.virtual_time;
+            let next_sequence = state.next_sequence;
+            state.next_sequence += 1;
+
+            let entry = state.tenants.entry(tenant.clone()).or_insert_with(|| {
+                let config = self.configs.get(&*tenant).cloned().unwrap_or_default();
+                TenantState {
+                    stride: STRIDE / u64::from(config.weight.max(1)),
+                    max_runners: config.max_runners.unwrap_or(usize::MAX),
+                    running: 0,
+                    virtual_time,
+                    waiters: BinaryHeap::new(),
+                }
+            });
+
+            let (tx, rx) = oneshot::channel();
+            entry.waiters.push(Waiter {
+                priority: scheduling.priority,
+                sequence: Reverse(next_sequence),
+                tx,
+            });
+            state.dispatch();
+            rx
+        };
+
+        let mut pending = PendingPermit { pool: self, tenant: tenant.clone(), rx: Some(rx) };
+        if let Some(rx) = &mut pending.rx {
+            // The waiters are only removed when being handed a runner
+            _ = rx.await;
+        }
+        pending.rx = None;
+        RunnerPermit { pool: self, tenant }
+    }
+
+    pub fn tenant_stats(&self) -> Vec<TenantStats> {
+        let state = self.state.lock().unwrap();
+        state
+            .tenants
+            .iter()
+            .map(|(tenant, state)| TenantStats {
+                tenant: tenant.clone(),
+                pending_count: state.waiters.iter().filter(|waiter| !waiter.tx.is_closed()).count()
+                    as u64,
+                running_count: state.running as u64,
+            })
+            .collect()
+    }
+
+    fn release(&self, tenant: &Arc<str>) {
+        let mut state = self.state.lock().unwrap();
+        state.available += 1;
+        if let Some(entry) = state.tenants.get_mut(tenant) {
+            entry.running -= 1;
+        }
+        state.dispatch();
+        state.remove_idle_tenant(tenant);
+    }
+
+    fn remove_idle_tenant(&self, tenant: &Arc<str>) {
+        self.state.lock().unwrap().remove_idle_tenant(tenant);
+    }
+}
+
+impl RunnerPoolState {
+    fn dispatch(&mut self) {
+        while self.available > 0 {
+            let candidate = self
+                .tenants
+                .iter()
+                .filter(|(_, state)| state.running < state.max_runners)
+                .filter_map(|(tenant, state)| {
+                    let waiter = state.waiters.peek()?;
+                    Some(((Reverse(state.virtual_time), waiter.priority, waiter.sequence), tenant))
+                })
+                .max_by_key(|(key, _)| *key)
+                .map(|(_, tenant)| tenant.clone());
+            let Some(tenant) = candidate else {
+                return;
+            };
+
+            let state = self.tenants.get_mut(&tenant).unwrap();
+            let waiter = state.waiters.pop().unwrap();
+            // The cancelled waiters are skipped
+            if waiter.tx.send(()).is_ok() {
+                self.available -= 1;
+                self.virtual_time = self.virtual_time.max(state.virtual_time);
+                state.virtual_time = state.virtual_time.max(self.virtual_time) + state.stride;
+                state.running += 1;
+            }
+        }
+    }
+
+    fn remove_idle_tenant(&mut self, tenant: &Arc<str>) {
+        if let Some(state) = self.tenants.get_mut(tenant) {
+            state.waiters.retain(|waiter| !waiter.tx.is_closed());
+            if state.running == 0 && state.waiters.is_empty() {
+                self.tenants.remove(tenant);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_util::FutureExt;
+    use indexmap::IndexMap;
+    use tokio::{sync::mpsc, task};
+
+    use super::{RunnerPool, Scheduling};
+    use crate::{conf::TenantConfig, entities::SubmissionPriority};
+
+    fn scheduling(priority: SubmissionPriority, tenant: &str) -> Scheduling {
+        Scheduling { priority, tenant: tenant.into() }
+    }
+
+    /// Wait until the given number of waiters are queued in the pool
+    async fn wait_pending(pool: &RunnerPool, count: u64) {
+        while pool.tenant_stats().iter().map(|stats| stats.pending_count).sum::<u64>() != count {
+            task::yield_now().await;
+        }
+    }
+
+    /// Queue the waiters one by one behind the held runner, and collect the
+    /// order in which they take the runner
+    async fn collect_order(
+        pool: Arc<RunnerPool>,
+        waiters: Vec<(&'static str, Scheduling)>,
+    ) -> Vec<&'static str> {
+        let permit = pool.acquire(&Scheduling::default()).await;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for (count, (name, scheduling)) in (1..).zip(waiters) {
+            tokio::spawn({
+                let pool = pool.clone();
+                let tx = tx.clone();
+                async move {
+                    let _permit = pool.acquire(&scheduling).await;
+                    tx.send(name).unwrap();
+                }
+            });
+            wait_pending(&pool, count).await;
+        }
+
+        drop(permit);
+        drop(tx);
+
+        let mut order = vec![];
+        while let Some(name) = rx.recv().await {
+            order.push(name);
+        }
+        order
+    }
+
+    #[tokio::test]
+    async fn test_priority() {
+        let pool = Arc::new(RunnerPool::new(1, &IndexMap::new()));
+
+        // The cancelled waiter is skipped
+        let permit = pool.acquire(&Scheduling::default()).await;
+        let cancelled = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                _ = pool.acquire(&scheduling(SubmissionPriority::High, "foo")).await;
+            }
+        });
+        wait_pending(&pool, 1).await;
+        cancelled.abort();
+        _ = cancelled.await;
+        drop(permit);
+
+        let order = collect_order(
+            pool.clone(),
+            vec![
+                ("low", scheduling(SubmissionPriority::Low, "foo")),
+                ("normal-1", scheduling(SubmissionPriority::Normal, "foo")),
+                ("high", scheduling(SubmissionPriority::High, "foo")),
+                ("normal-2", scheduling(SubmissionPriority::Normal, "foo")),
+            ],
+        )
+        .await;
+        assert_eq!(order, ["high", "normal-1", "normal-2", "low"]);
+        assert!(pool.tenant_stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tenants() {
+        let configs =
+            IndexMap::from([("foo".to_owned(), TenantConfig { weight: 2, max_runners: None })]);
+        let pool = Arc::new(RunnerPool::new(1, &configs));
+
+        let mut waiters = vec![];
+        for name in ["foo-1", "foo-2", "foo-3", "foo-4"] {
+            waiters.push((name, scheduling(SubmissionPriority::Normal, "foo")));
+        }
+        for name in ["bar-1", "bar-2"] {
+            waiters.push((name, scheduling(SubmissionPriority::Normal, "bar")));
+        }
+
+        // The tenant `foo` takes two runners for each one taken by `bar`
+        let order = collect_order(pool, waiters).await;
+        assert_eq!(order, ["foo-1", "bar-1", "foo-2", "foo-3", "bar-2", "foo-4"]);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_priority() {
+        let pool = Arc::new(RunnerPool::new(1, &IndexMap::new()));
+
+        // The high priority only takes effect within the tenant, or breaks the tie
+        let order = collect_order(
+            pool,
+            vec![
+                ("bar-1", scheduling(SubmissionPriority::Normal, "bar")),
+                ("bar-2", scheduling(SubmissionPriority::Normal, "bar")),
+                ("foo-1", scheduling(SubmissionPriority::High, "foo")),
+                ("foo-2", scheduling(SubmissionPriority::High, "foo")),
+                ("foo-3", scheduling(SubmissionPriority::Low, "foo")),
+                ("foo-4", scheduling(SubmissionPriority::High, "foo")),
+            ],
+        )
+        .await;
+        assert_eq!(order, ["foo-1", "bar-1", "foo-2", "bar-2", "foo-4", "foo-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_max_runners() {
+        let configs =
+            IndexMap::from([("foo".to_owned(), TenantConfig { weight: 1, max_runners: Some(1) })]);
+        let pool = RunnerPool::new(2, &configs);
+
+        let foo = scheduling(SubmissionPriority::Normal, "foo");
+        let _first = pool.acquire(&foo).await;
+        assert!(pool.acquire(&foo).now_or_never().is_none());
+
+        let _bar = pool.acquire(&scheduling(SubmissionPriority::Normal, "bar")).await;
+        let stats = pool.tenant_stats();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|stats| stats.running_count == 1 && stats.pending_count == 0));
+    }
+}
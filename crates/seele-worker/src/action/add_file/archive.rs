@@ -0,0 +1,321 @@
+use std::{
+    ffi::OsStr,
+    fs::{self, File, Permissions},
+    io::{self, Read},
+    os::{fd::OwnedFd, unix::prelude::PermissionsExt},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use nix::{
+    errno::Errno,
+    fcntl::{self, OFlag},
+    sys::stat::{self, Mode},
+    unistd::{self, UnlinkatFlags},
+};
+use seele_shared::entities::add_file::ArchiveFormat;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    pub max_size_bytes: u64,
+    pub max_entries: u64,
+}
+
+/// Unpack the archive into the target directory. Entries escaping the target
+/// directory, symbolic links and hard links are rejected. The entries are
+/// created relative to the opened target directory without following the
+/// symbolic links already in it, which the earlier tasks may have created.
+pub fn extract_archive(
+    format: ArchiveFormat,
+    archive_path: &Path,
+    target: &Path,
+    limits: ExtractLimits,
+) -> Result<()> {
+    fs::create_dir_all(target).context("Error creating the target directory")?;
+    let target = fcntl::open(target, DIRECTORY_FLAGS, Mode::empty())
+        .context("Error opening the target directory")?;
+
+    let file = File::open(archive_path).context("Error opening the archive")?;
+    let mut extractor = Extractor { target, limits, entries: 0, size_bytes: 0 };
+    match format {
+        ArchiveFormat::Zip => extractor.extract_zip(file),
+        ArchiveFormat::Tar => extractor.extract_tar(file),
+        ArchiveFormat::TarGz => extractor.extract_tar(flate2::read::GzDecoder::new(file)),
+        ArchiveFormat::TarZst => extractor.extract_tar(
+            zstd::stream::read::Decoder::new(file).context("Error initializing zstd decoder")?,
+        ),
+    }
+}
+
+const DIRECTORY_FLAGS: OFlag = OFlag::O_DIRECTORY.union(OFlag::O_NOFOLLOW).union(OFlag::O_CLOEXEC);
+
+struct Extractor {
+    target: OwnedFd,
+    limits: ExtractLimits,
+    entries: u64,
+    size_bytes: u64,
+}
+
+impl Extractor {
+    fn extract_tar(&mut self, reader: impl Read) -> Result<()> {
+        use tar::EntryType;
+
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries().context("Error reading the archive")? {
+            let mut entry = entry.context("Error reading the archive entry")?;
+            let path = entry.path().context("Error reading the entry path")?.into_owned();
+
+            match entry.header().entry_type() {
+                EntryType::Directory => self.create_directory(&path)?,
+                EntryType::Regular | EntryType::Continuous => {
+                    let mode = entry.header().mode().unwrap_or(0o644);
+                    self.create_file(&path, mode, &mut entry)?
+                }
+                // Metadata entries of pax and gnu formats are handled by the tar crate
+                EntryType::XGlobalHeader => continue,
+                kind => bail!("Unsupported entry type {kind:?}: {}", path.display()),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract_zip(&mut self, file: File) -> Result<()> {
+        let mut archive = zip::ZipArchive::new(file).context("Error reading the archive")?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).context("Error reading the archive entry")?;
+            let path = PathBuf::from(entry.name());
+
+            if entry.is_symlink() {
+                bail!("Unsupported entry type Symlink: {}", path.display());
+            }
+
+            if entry.is_dir() {
+                self.create_directory(&path)?;
+            } else {
+                let mode = entry.unix_mode().unwrap_or(0o644);
+                self.create_file(&path, mode, &mut entry)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_directory(&mut self, path: &Path) -> Result<()> {
+        let components = self.resolve_entry(path)?;
+        self.open_directory(path, &components)?;
+        Ok(())
+    }
+
+    fn create_file(&mut self, path: &Path, mode: u32, reader: &mut impl Read) -> Result<()> {
+        let components = self.resolve_entry(path)?;
+        let (name, parents) = components.split_last().unwrap();
+        let directory = self.open_directory(path, parents)?;
+
+        // The existing file is replaced instead of truncated, since it may be a hard
+        // link shared with the other submissions
+        match unistd::unlinkat(&directory, *name, UnlinkatFlags::NoRemoveDir) {
+            Ok(()) | Err(Errno::ENOENT) => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Error removing the file: {}", path.display()));
+            }
+        }
+        let mut file = File::from(
+            fcntl::openat(
+                &directory,
+                *name,
+                OFlag::O_WRONLY
+                    | OFlag::O_CREAT
+                    | OFlag::O_EXCL
+                    | OFlag::O_NOFOLLOW
+                    | OFlag::O_CLOEXEC,
+                Mode::from_bits_truncate(0o644),
+            )
+            .with_context(|| format!("Error creating the file: {}", path.display()))?,
+        );
+
+        // Do not trust the sizes recorded in the archive
+        let remaining = self.limits.max_size_bytes - self.size_bytes;
+        let written = io::copy(&mut reader.take(remaining + 1), &mut file)
+            .with_context(|| format!("Error writing the file: {}", path.display()))?;
+        if written > remaining {
+            bail!(
+                "The size of the extracted files exceeds the limit: {}",
+                self.limits.max_size_bytes
+            );
+        }
+        self.size_bytes += written;
+
+        let mode = if mode & 0o111 != 0 { 0o755 } else { 0o644 };
+        file.set_permissions(Permissions::from_mode(mode))
+            .with_context(|| format!("Error setting the permission of {}", path.display()))
+    }
+
+    /// Open the directory under the target directory, creating the missing ones
+    fn open_directory(&self, path: &Path, components: &[&OsStr]) -> Result<OwnedFd> {
+        let mut directory = self.target.try_clone().context("Error opening the directory")?;
+        for component in components {
+            match stat::mkdirat(&directory, *component, Mode::from_bits_truncate(0o777)) {
+                Ok(()) | Err(Errno::EEXIST) => {}
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Error creating the directory: {}", path.display())
+                    });
+                }
+            }
+            directory = fcntl::openat(&directory, *component, DIRECTORY_FLAGS, Mode::empty())
+                .with_context(|| format!("Error opening the directory: {}", path.display()))?;
+        }
+        Ok(directory)
+    }
+
+    fn resolve_entry<'a>(&mut self, path: &'a Path) -> Result<Vec<&'a OsStr>> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            bail!(
+                "The count of the archive entries exceeds the limit: {}",
+                self.limits.max_entries
+            );
+        }
+
+        let mut components = vec![];
+        for component in path.components() {
+            match component {
+                Component::Normal(item) => components.push(item),
+                Component::CurDir => {}
+                _ => bail!("Illegal entry path: {}", path.display()),
+            }
+        }
+
+        if components.is_empty() {
+            bail!("Illegal entry path: {}", path.display());
+        }
+
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Cursor, Write},
+        os::unix,
+    };
+
+    use seele_shared::entities::add_file::ArchiveFormat;
+
+    use super::{ExtractLimits, extract_archive};
+
+    const LIMITS: ExtractLimits = ExtractLimits { max_size_bytes: 1024, max_entries: 8 };
+
+    fn make_tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn make_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        for (path, content) in files {
+            writer.start_file(*path, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn run(format: ArchiveFormat, data: &[u8]) -> anyhow::Result<Vec<String>> {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        fs::write(root.join("archive"), data).unwrap();
+
+        extract_archive(format, &root.join("archive"), &root.join("out"), LIMITS).map(|_| {
+            let mut files = fs::read_dir(root.join("out/data"))
+                .unwrap()
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    let content = fs::read_to_string(&path).unwrap();
+                    format!("{}:{content}", path.file_name().unwrap().to_string_lossy())
+                })
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        })
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        let files = [("data/1.in", "114"), ("./data/1.out", "514")];
+        let expected = vec!["1.in:114".to_owned(), "1.out:514".to_owned()];
+
+        let tar = make_tar(&files);
+        assert_eq!(run(ArchiveFormat::Tar, &tar).unwrap(), expected);
+
+        let tar_gz = {
+            let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(&tar).unwrap();
+            encoder.finish().unwrap()
+        };
+        assert_eq!(run(ArchiveFormat::TarGz, &tar_gz).unwrap(), expected);
+
+        let tar_zst = zstd::encode_all(&tar[..], 0).unwrap();
+        assert_eq!(run(ArchiveFormat::TarZst, &tar_zst).unwrap(), expected);
+
+        let zip = make_zip(&files);
+        assert_eq!(run(ArchiveFormat::Zip, &zip).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_extract_archive_malicious() {
+        let cases = [
+            ("tar_traversal", ArchiveFormat::Tar, make_tar(&[("../evil", "x")])),
+            ("tar_absolute", ArchiveFormat::Tar, make_tar(&[("/evil", "x")])),
+            ("zip_traversal", ArchiveFormat::Zip, make_zip(&[("data/../../evil", "x")])),
+            ("zip_size", ArchiveFormat::Zip, make_zip(&[("data/big", &"x".repeat(1025))])),
+            (
+                "tar_entries",
+                ArchiveFormat::Tar,
+                make_tar(&(0..9).map(|_| ("data/file", "x")).collect::<Vec<_>>()),
+            ),
+        ];
+
+        for (name, format, data) in cases {
+            assert!(run(format, &data).is_err(), "case {name}");
+        }
+    }
+
+    #[test]
+    fn test_extract_archive_symlink() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let outside = root.join("outside");
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("1.in"), "host").unwrap();
+        fs::write(root.join("archive"), make_tar(&[("data/1.in", "114")])).unwrap();
+
+        // The planted file link is replaced instead of followed
+        fs::create_dir_all(root.join("out/data")).unwrap();
+        unix::fs::symlink(outside.join("1.in"), root.join("out/data/1.in")).unwrap();
+        extract_archive(ArchiveFormat::Tar, &root.join("archive"), &root.join("out"), LIMITS)
+            .unwrap();
+        assert_eq!(fs::read_to_string(root.join("out/data/1.in")).unwrap(), "114");
+        assert_eq!(fs::read_to_string(outside.join("1.in")).unwrap(), "host");
+
+        // The planted directory link is rejected
+        fs::remove_dir_all(root.join("out/data")).unwrap();
+        unix::fs::symlink(&outside, root.join("out/data")).unwrap();
+        assert!(
+            extract_archive(ArchiveFormat::Tar, &root.join("archive"), &root.join("out"), LIMITS)
+                .is_err()
+        );
+        assert_eq!(fs::read_to_string(outside.join("1.in")).unwrap(), "host");
+    }
+}
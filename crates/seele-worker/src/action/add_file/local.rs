@@ -0,0 +1,205 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::{fd::AsRawFd, unix::fs::MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use super::FileDigest;
+use crate::conf::LocalFileStrategy;
+
+// FICLONE from linux/fs.h
+nix::ioctl_write_int!(ficlone, 0x94, 9);
+
+/// Add the local file to the target path. The digest is only returned when the
+/// data is actually copied. Hard links are never made to the source, which
+/// would share its inode with the submission, so the file is copied instead and
+/// the caller links the sealed copy in the data store.
+pub fn add_local_file(
+    target: &Path,
+    source: &Path,
+    roots: &[PathBuf],
+    strategy: LocalFileStrategy,
+) -> Result<Option<FileDigest>> {
+    let source = check_source(source, roots)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).context("Error creating the directories")?;
+    }
+
+    match strategy {
+        LocalFileStrategy::Copy | LocalFileStrategy::HardLink => {
+            copy_file(&source, target).map(Some)
+        }
+        LocalFileStrategy::Reflink => {
+            let source = File::open(&source).context("Error opening the file")?;
+            let target = create_target(target)?;
+
+            // SAFETY: Both file descriptors are valid during the call
+            match unsafe { ficlone(target.as_raw_fd(), source.as_raw_fd() as _) } {
+                Ok(_) => Ok(None),
+                Err(err) => {
+                    warn!("Error cloning the local file, falling back to copying: {err}");
+                    copy_hashed(source, target).map(Some)
+                }
+            }
+        }
+    }
+}
+
+/// Resolve the local file and make the key of its copy in the data store, which
+/// changes once the file is modified
+pub fn make_store_key(source: &Path, roots: &[PathBuf]) -> Result<(PathBuf, String)> {
+    let source = check_source(source, roots)?;
+    let metadata = source.metadata().context("Error reading the metadata")?;
+    let key = format!(
+        "local:{}:{}:{}.{}:{}",
+        source.display(),
+        metadata.ino(),
+        metadata.mtime(),
+        metadata.mtime_nsec(),
+        metadata.len()
+    );
+    Ok((source, key))
+}
+
+pub fn digest_file(path: &Path) -> Result<FileDigest> {
+    copy_hashed(File::open(path).context("Error opening the file")?, io::sink())
+}
+
+fn check_source(source: &Path, roots: &[PathBuf]) -> Result<PathBuf> {
+    if roots.is_empty() {
+        bail!("Local files are not allowed");
+    }
+
+    let source = fs::canonicalize(source).context("Error resolving the local file")?;
+    if !roots.iter().any(|root| fs::canonicalize(root).is_ok_and(|root| source.starts_with(root))) {
+        bail!("The local file is outside the allowed directories: {}", source.display());
+    }
+
+    if !source.metadata().context("Error reading the metadata")?.is_file() {
+        bail!("The local file is not a regular file: {}", source.display());
+    }
+
+    Ok(source)
+}
+
+fn copy_file(source: &Path, target: &Path) -> Result<FileDigest> {
+    copy_hashed(File::open(source).context("Error opening the file")?, create_target(target)?)
+}
+
+/// Unlink the existing target before creating it, since it may be a hard link
+/// to a data store entry which truncating would rewrite
+fn create_target(target: &Path) -> Result<File> {
+    match fs::remove_file(target) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            return Err(err).context("Error removing the existing file");
+        }
+        _ => {}
+    }
+
+    File::create(target).context("Error creating the file")
+}
+
+fn copy_hashed(mut reader: impl Read, mut writer: impl Write) -> Result<FileDigest> {
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let count = reader.read(&mut buffer).context("Error reading the file")?;
+        if count == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..count]);
+        size += count as u64;
+        writer.write_all(&buffer[..count]).context("Error copying the file")?;
+    }
+
+    Ok(FileDigest::new(hasher, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, Permissions},
+        os::unix::fs::{MetadataExt, PermissionsExt},
+        path::PathBuf,
+    };
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::{add_local_file, make_store_key};
+    use crate::{action::add_file::store, conf::LocalFileStrategy, shared};
+
+    #[test]
+    fn test_add_local_file() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::write(root.join("data/1.in"), "114514").unwrap();
+        fs::write(root.join("secret"), "1919810").unwrap();
+
+        let roots = [root.join("data")];
+        let run = |name: &str, source: &str, roots: &[PathBuf], strategy| {
+            add_local_file(&root.join(name), &root.join(source), roots, strategy)
+        };
+
+        let copied = run("copied", "data/1.in", &roots, LocalFileStrategy::Copy);
+        let escaped = run("escaped", "data/../secret", &roots, LocalFileStrategy::Copy);
+        let disallowed = run("disallowed", "data/1.in", &[], LocalFileStrategy::Copy);
+
+        assert_eq!(copied.unwrap().unwrap().size, 6);
+        assert_eq!(fs::read_to_string(root.join("copied")).unwrap(), "114514");
+        assert!(escaped.is_err());
+        assert!(disallowed.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_link_local_file() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let source = root.join("data/1.in");
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::write(&source, "114514").unwrap();
+        fs::set_permissions(&source, Permissions::from_mode(0o644)).unwrap();
+
+        // The way the data store links the local file to the submission
+        let roots = [root.join("data")];
+        let (resolved, key) = make_store_key(&source, &roots).unwrap();
+        add_local_file(&root.join("entry"), &resolved, &roots, LocalFileStrategy::HardLink)
+            .unwrap();
+        store::seal_entry(&root.join("entry")).unwrap();
+        store::link_entry(&root.join("entry"), &root.join("linked")).unwrap();
+
+        // Writing the submission files replaces the links instead of reaching the
+        // data store entry or the source
+        let linked = root.join("linked");
+        let mut file = shared::file::create_file(&linked).await.unwrap();
+        file.write_all(b"1919810").await.unwrap();
+        file.flush().await.unwrap();
+        fs::write(root.join("other"), "1919810").unwrap();
+        store::link_entry(&root.join("entry"), &root.join("copied")).unwrap();
+        add_local_file(
+            &root.join("copied"),
+            &root.join("other"),
+            &[root.into()],
+            LocalFileStrategy::Copy,
+        )
+        .unwrap();
+
+        let metadata = fs::metadata(&source).unwrap();
+        assert_ne!(metadata.ino(), fs::metadata(&linked).unwrap().ino());
+        assert_eq!(metadata.mode() & 0o777, 0o644);
+        assert_eq!(fs::read_to_string(&source).unwrap(), "114514");
+        assert_eq!(fs::read_to_string(root.join("entry")).unwrap(), "114514");
+        assert_eq!(fs::read_to_string(&linked).unwrap(), "1919810");
+        assert_eq!(fs::read_to_string(root.join("copied")).unwrap(), "1919810");
+
+        fs::write(&source, "1919810").unwrap();
+        assert_ne!(make_store_key(&source, &roots).unwrap().1, key);
+    }
+}
@@ -0,0 +1,671 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, future};
+use http_cache::{CacheManager, HttpCacheOptions, MokaManager};
+use seele_shared::entities::add_file::*;
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    task::spawn_blocking,
+};
+use tracing::{info, instrument, warn};
+use triggered::Listener;
+
+use self::archive::ExtractLimits;
+use super::ActionContext;
+use crate::{
+    conf::{self, LocalFileStrategy},
+    entities::{ActionFailureReportExt, ActionReportExt, ActionSuccessReportExt},
+    shared::{
+        self,
+        cache::{self, CacheCounter, CacheStats, ManagedCache},
+    },
+};
+
+mod archive;
+mod local;
+mod s3;
+mod store;
+mod template;
+
+#[instrument(skip_all, name = "action_add_file_execute")]
+pub async fn execute(
+    handle: Listener,
+    ctx: &ActionContext,
+    config: &Config,
+) -> Result<ActionReportExt> {
+    let results = future::join_all(config.files.iter().map(|item| {
+        let handle = handle.clone();
+        async move {
+            let path = item.path.resolve(&ctx.submission_root).await?;
+            match store::make_key(item)? {
+                None => add_file(handle, ctx, item, &path, &ctx.submission_root).await,
+                Some(key) => {
+                    store::link(key, &path, |staging_path| async move {
+                        add_file(handle, ctx, item, &staging_path, &conf::PATHS.store).await
+                    })
+                    .await
+                }
+            }
+        }
+    }))
+    .await;
+
+    let failed_items: Vec<_> = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, result)| {
+            result.as_ref().err().map(|err| format!("{}: {:#}", config.files[i], err))
+        })
+        .collect();
+
+    if !failed_items.is_empty() {
+        return Ok(ActionReportExt::Failure(ActionFailureReportExt::AddFile(FailedReport {
+            files: failed_items,
+        })));
+    }
+
+    Ok(ActionReportExt::Success(ActionSuccessReportExt::AddFile))
+}
+
+/// Add the file to the path. Archives are downloaded into the scratch directory
+/// before being extracted.
+async fn add_file(
+    handle: Listener,
+    ctx: &ActionContext,
+    item: &FileItem,
+    path: &Path,
+    scratch_directory: &Path,
+) -> Result<()> {
+    let Some(format) = item.extract else {
+        let digest = write_file(handle, ctx, path, item).await?;
+        return verify_digest(item, digest, path).await;
+    };
+
+    let archive_path = scratch_directory.join(format!(".seele-archive-{}", nano_id::base62::<8>()));
+    let result = async {
+        let digest = write_file(handle, ctx, &archive_path, item).await?;
+        verify_digest(item, digest, &archive_path).await?;
+        handle_extract(format, archive_path.clone(), path.to_owned()).await
+    }
+    .await;
+
+    _ = fs::remove_file(&archive_path).await;
+    result
+}
+
+/// The sha256 hash and the size of a written file
+#[derive(Debug, Clone)]
+struct FileDigest {
+    sha256: String,
+    size: u64,
+}
+
+impl FileDigest {
+    fn new(hasher: Sha256, size: u64) -> Self {
+        Self { sha256: format!("{:x}", hasher.finalize()), size }
+    }
+
+    fn of(data: &[u8]) -> Self {
+        Self::new(Sha256::new_with_prefix(data), data.len() as u64)
+    }
+}
+
+/// Write the file to the path. The digest is absent when the file is linked
+/// instead of being written.
+async fn write_file(
+    handle: Listener,
+    ctx: &ActionContext,
+    path: &Path,
+    item: &FileItem,
+) -> Result<Option<FileDigest>> {
+    let create_file =
+        || async { shared::file::create_file(path).await.context("Error creating the file") };
+
+    Ok(Some(match &item.ext {
+        FileItemExt::PlainText { plain } => handle_plain_text(create_file().await?, plain).await?,
+        FileItemExt::Http { url } => {
+            handle_http_url(handle, create_file().await?, url, item.sha256.as_deref()).await?
+        }
+        FileItemExt::S3 { s3 } => handle_s3(handle, create_file().await?, s3).await?,
+        FileItemExt::Base64 { base64 } => handle_base64(create_file().await?, base64).await?,
+        FileItemExt::LocalPath { local } => return handle_local_path(path, local).await,
+        FileItemExt::Template { template, vars } => {
+            let text = template::render(template, vars, &ctx.tags)?;
+            handle_plain_text(create_file().await?, &text).await?
+        }
+        FileItemExt::Attachment { attachment } => {
+            return handle_attachment(path, ctx, attachment).await;
+        }
+    }))
+}
+
+async fn verify_digest(item: &FileItem, digest: Option<FileDigest>, path: &Path) -> Result<()> {
+    if item.sha256.is_none() && item.size.is_none() {
+        return Ok(());
+    }
+
+    let digest = match digest {
+        Some(digest) => digest,
+        None => {
+            let path = path.to_owned();
+            spawn_blocking(move || local::digest_file(&path)).await??
+        }
+    };
+
+    if let Some(size) = item.size
+        && size != digest.size
+    {
+        bail!("Size mismatch, expected {size} bytes but got {} bytes", digest.size);
+    }
+
+    if let Some(sha256) = &item.sha256
+        && !sha256.eq_ignore_ascii_case(&digest.sha256)
+    {
+        bail!("Sha256 mismatch, expected {sha256} but got {}", digest.sha256);
+    }
+
+    Ok(())
+}
+
+async fn handle_extract(
+    format: ArchiveFormat,
+    archive_path: PathBuf,
+    target: PathBuf,
+) -> Result<()> {
+    let config = &conf::CONFIG.worker.action.add_file;
+    let limits = ExtractLimits {
+        max_size_bytes: config.extract_max_size_mib * 1024 * 1024,
+        max_entries: config.extract_max_entries,
+    };
+
+    spawn_blocking(move || archive::extract_archive(format, &archive_path, &target, limits))
+        .await?
+        .context("Error extracting the archive")
+}
+
+async fn handle_plain_text(mut file: File, text: &str) -> Result<FileDigest> {
+    file.write_all(text.as_bytes()).await.context("Error writing the file")?;
+    Ok(FileDigest::of(text.as_bytes()))
+}
+
+async fn handle_base64(mut file: File, base64: &str) -> Result<FileDigest> {
+    use base64::prelude::*;
+
+    let (data, digest) = spawn_blocking({
+        let base64 = base64.to_owned();
+        move || {
+            BASE64_STANDARD_NO_PAD.decode(base64).map(|data| {
+                let digest = FileDigest::of(&data);
+                (data, digest)
+            })
+        }
+    })
+    .await?
+    .context("Error decoding base64 text")?;
+    file.write_all(&data).await.context("Error writing the file")?;
+    Ok(digest)
+}
+
+async fn handle_local_path(target: &Path, source: &Path) -> Result<Option<FileDigest>> {
+    let config = &conf::CONFIG.worker.action.add_file;
+    if let LocalFileStrategy::HardLink = config.local_strategy {
+        // Link a sealed copy in the data store, since the submission could change
+        // the mode or the content of the source via a hard link to it
+        let source = source.to_owned();
+        let (source, key) =
+            spawn_blocking(move || local::make_store_key(&source, &config.local_roots)).await??;
+        store::link(key, target, |staging_path| async move {
+            spawn_blocking(move || {
+                local::add_local_file(
+                    &staging_path,
+                    &source,
+                    &config.local_roots,
+                    LocalFileStrategy::Copy,
+                )
+            })
+            .await?
+            .map(|_| ())
+        })
+        .await?;
+        return Ok(None);
+    }
+
+    let (target, source) = (target.to_owned(), source.to_owned());
+    spawn_blocking(move || {
+        local::add_local_file(&target, &source, &config.local_roots, config.local_strategy)
+    })
+    .await?
+}
+
+async fn handle_attachment(
+    target: &Path,
+    ctx: &ActionContext,
+    name: &str,
+) -> Result<Option<FileDigest>> {
+    let Some(source) = ctx.attachments.get(name) else {
+        bail!("The submission has no attachment named {name}");
+    };
+
+    // Copy the attachment since it can be referenced by multiple files
+    let (target, source) = (target.to_owned(), source.to_owned());
+    spawn_blocking(move || {
+        local::add_local_file(
+            &target,
+            &source,
+            std::slice::from_ref(&conf::PATHS.attachments),
+            LocalFileStrategy::Copy,
+        )
+    })
+    .await?
+}
+
+static S3_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(shared::http::build_http_client);
+
+#[instrument(skip(handle, file))]
+async fn handle_s3(handle: Listener, mut file: File, object: &S3Object) -> Result<FileDigest> {
+    let Some(config) = &conf::CONFIG.worker.action.add_file.s3 else {
+        bail!("No S3 storage is configured");
+    };
+
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    tokio::select! {
+        _ = handle => bail!(shared::ABORTED_MESSAGE),
+        result = s3::download_object(&S3_CLIENT, config, object) => match result {
+            Err(err) => bail!("Error downloading the object: {err:#}"),
+            Ok(mut stream) => {
+                while let Some(data) = stream.next().await {
+                    let data = data.context("Error reading the remote data")?;
+                    hasher.update(&data);
+                    size += data.len() as u64;
+                    file.write_all(&data).await.context("Error writing to the file")?;
+                }
+            }
+        }
+    }
+
+    Ok(FileDigest::new(hasher, size))
+}
+
+const HTTP_CACHE_NAME: &str = "seele-add-file";
+
+static HTTP_CACHE: LazyLock<moka::future::Cache<String, Arc<Vec<u8>>>> = LazyLock::new(|| {
+    use std::time::Duration;
+
+    let config = &conf::CONFIG.worker.action.add_file;
+    moka::future::Cache::builder()
+        .name(HTTP_CACHE_NAME)
+        .weigher(|_, value: &Arc<Vec<u8>>| -> u32 { value.len().try_into().unwrap_or(u32::MAX) })
+        .max_capacity(1024 * 1024 * config.cache_size_mib)
+        .time_to_idle(Duration::from_secs(60 * 60 * config.cache_ttl_hour))
+        .support_invalidation_closures()
+        .build()
+});
+
+static HTTP_CACHE_COUNTER: CacheCounter = CacheCounter::new();
+
+static HTTP_CACHE_MANAGER: LazyLock<MokaManager> =
+    LazyLock::new(|| MokaManager::new(HTTP_CACHE.clone()));
+
+/// The key of a request in the http cache, defined here instead of relying on
+/// the default one so that the bad copies can be deleted
+fn http_cache_key(method: &str, url: &str) -> String {
+    format!("{method}:{url}")
+}
+
+const VERIFIED_CACHE_NAME: &str = "seele-add-file-verified";
+
+#[derive(Debug)]
+struct VerifiedFile {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Downloaded files whose sha256 hashes have been verified, keyed by the
+/// hashes. They are kept in the data store directory and served without
+/// sending any request. The files are removed from the disk when they are
+/// evicted.
+static VERIFIED_CACHE: LazyLock<moka::future::Cache<String, Arc<VerifiedFile>>> =
+    LazyLock::new(|| {
+        use std::time::Duration;
+
+        let config = &conf::CONFIG.worker.action.add_file;
+        moka::future::Cache::builder()
+            .name(VERIFIED_CACHE_NAME)
+            .weigher(|_, file: &Arc<VerifiedFile>| -> u32 {
+                file.size.try_into().unwrap_or(u32::MAX)
+            })
+            .max_capacity(1024 * 1024 * config.cache_size_mib)
+            .time_to_idle(Duration::from_secs(60 * 60 * config.cache_ttl_hour))
+            .eviction_listener(|_, file: Arc<VerifiedFile>, _| {
+                if let Err(err) = std::fs::remove_file(&file.path) {
+                    warn!(path = %file.path.display(), "Error removing the verified copy: {err:#}");
+                }
+            })
+            .support_invalidation_closures()
+            .build()
+    });
+
+static VERIFIED_CACHE_COUNTER: CacheCounter = CacheCounter::new();
+
+static HTTP_CLIENT: LazyLock<reqwest_middleware::ClientWithMiddleware> = LazyLock::new(|| {
+    use http_cache_reqwest::{Cache, HttpCache};
+    use reqwest_middleware::ClientBuilder;
+
+    ClientBuilder::new(shared::http::build_http_client())
+        .with(Cache(HttpCache {
+            mode: conf::CONFIG.worker.action.add_file.cache_strategy.into(),
+            manager: HTTP_CACHE_MANAGER.clone(),
+            options: HttpCacheOptions {
+                cache_key: Some(Arc::new(|parts: &http::request::Parts| {
+                    http_cache_key(parts.method.as_str(), &parts.uri.to_string())
+                })),
+                ..Default::default()
+            },
+        }))
+        .build()
+});
+
+struct HttpFileCache;
+
+impl ManagedCache for HttpFileCache {
+    fn name(&self) -> &'static str {
+        HTTP_CACHE_NAME
+    }
+
+    fn stats(&self) -> CacheStats {
+        HTTP_CACHE_COUNTER.make_stats(
+            HTTP_CACHE_NAME,
+            HTTP_CACHE.entry_count(),
+            HTTP_CACHE.weighted_size(),
+        )
+    }
+
+    fn keys(&self) -> Vec<String> {
+        HTTP_CACHE.iter().map(|(key, _)| key.to_string()).collect()
+    }
+
+    fn purge(&self, prefix: Option<&str>) -> u64 {
+        let Some(prefix) = prefix else {
+            let count = HTTP_CACHE.entry_count();
+            HTTP_CACHE.invalidate_all();
+            return count;
+        };
+
+        let prefix = prefix.to_owned();
+        let count = HTTP_CACHE.iter().filter(|(key, _)| key.starts_with(&prefix)).count();
+        _ = HTTP_CACHE.invalidate_entries_if(move |key, _| key.starts_with(&prefix));
+        count as u64
+    }
+}
+
+struct VerifiedFileCache;
+
+impl ManagedCache for VerifiedFileCache {
+    fn name(&self) -> &'static str {
+        VERIFIED_CACHE_NAME
+    }
+
+    fn stats(&self) -> CacheStats {
+        VERIFIED_CACHE_COUNTER.make_stats(
+            VERIFIED_CACHE_NAME,
+            VERIFIED_CACHE.entry_count(),
+            VERIFIED_CACHE.weighted_size(),
+        )
+    }
+
+    fn keys(&self) -> Vec<String> {
+        VERIFIED_CACHE.iter().map(|(key, _)| key.to_string()).collect()
+    }
+
+    fn purge(&self, prefix: Option<&str>) -> u64 {
+        let Some(prefix) = prefix else {
+            let count = VERIFIED_CACHE.entry_count();
+            VERIFIED_CACHE.invalidate_all();
+            return count;
+        };
+
+        let prefix = prefix.to_lowercase();
+        let count = VERIFIED_CACHE.iter().filter(|(key, _)| key.starts_with(&prefix)).count();
+        _ = VERIFIED_CACHE.invalidate_entries_if(move |key, _| key.starts_with(&prefix));
+        count as u64
+    }
+}
+
+pub fn init() {
+    LazyLock::force(&HTTP_CLIENT);
+    LazyLock::force(&VERIFIED_CACHE);
+    cache::register(Arc::new(HttpFileCache));
+    cache::register(Arc::new(VerifiedFileCache));
+    store::init();
+}
+
+#[instrument(skip(handle, file))]
+async fn handle_http_url(
+    handle: Listener,
+    mut file: File,
+    url: &str,
+    sha256: Option<&str>,
+) -> Result<FileDigest> {
+    let sha256 = sha256.map(str::to_lowercase);
+    if let Some(sha256) = &sha256 {
+        let verified = VERIFIED_CACHE.get(sha256).await;
+        VERIFIED_CACHE_COUNTER.record(verified.is_some());
+        // The copy might be evicted after the lookup, in which case the file is
+        // downloaded again
+        if let Some(mut source) = match verified {
+            None => None,
+            Some(verified) => File::open(&verified.path).await.ok(),
+        } {
+            info!("Serving the verified copy");
+            let size = tokio::io::copy(&mut source, &mut file)
+                .await
+                .context("Error writing to the file")?;
+            return Ok(FileDigest { sha256: sha256.clone(), size });
+        }
+    }
+
+    // Only keep a copy when it could become a verified one
+    let copy_path = sha256
+        .as_ref()
+        .map(|_| conf::PATHS.store.join(format!("verified-{}", nano_id::base62::<16>())));
+    let result = async {
+        let mut copy = match &copy_path {
+            None => None,
+            Some(path) => Some(shared::file::create_file(path).await?),
+        };
+
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        tokio::select! {
+            _ = handle => bail!(shared::ABORTED_MESSAGE),
+            result = download_http_file(url) => match result {
+                Err(err) => bail!("Error downloading the file: {err:#}"),
+                Ok(mut stream) => {
+                    while let Some(data) = stream.next().await {
+                        let data = data.context("Error reading the remote data")?;
+                        hasher.update(&data);
+                        size += data.len() as u64;
+                        file.write_all(&data).await.context("Error writing to the file")?;
+                        if let Some(copy) = &mut copy {
+                            copy.write_all(&data).await.context("Error writing the copy")?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(copy) = &mut copy {
+            copy.flush().await.context("Error writing the copy")?;
+        }
+        Ok(FileDigest::new(hasher, size))
+    }
+    .await;
+
+    let Some((sha256, copy_path)) = sha256.zip(copy_path) else {
+        return result;
+    };
+
+    match result {
+        Ok(digest) if digest.sha256 == sha256 => {
+            let verified = VerifiedFile { path: copy_path, size: digest.size };
+            VERIFIED_CACHE.insert(sha256, Arc::new(verified)).await;
+            Ok(digest)
+        }
+        result => {
+            _ = fs::remove_file(&copy_path).await;
+            if result.is_ok()
+                && let Ok(url) = reqwest::Url::parse(url)
+            {
+                // Do not let the http cache serve the bad copy again
+                _ = HTTP_CACHE_MANAGER.delete(&http_cache_key("GET", url.as_str())).await;
+            }
+            result
+        }
+    }
+}
+
+async fn download_http_file(
+    url: &str,
+) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>> {
+    let response = HTTP_CLIENT
+        .get(url)
+        .send()
+        .await
+        .context("Error sending the request")?
+        .error_for_status()
+        .context("Got a non-ok response")?;
+
+    let headers = response.headers();
+    HTTP_CACHE_COUNTER.record(matches!(
+        headers.get(http_cache::XCACHE).and_then(|value| value.to_str().ok()),
+        Some("HIT")
+    ));
+
+    match (headers.get(http_cache::XCACHE), headers.get(http_cache::XCACHELOOKUP)) {
+        (Some(cache), Some(cache_lookup)) => {
+            info!("Cache served: {:?}, cache existed: {:?}", cache, cache_lookup);
+        }
+        (Some(cache), None) => {
+            info!("Cache served: {:?}", cache);
+        }
+        (None, Some(cache_lookup)) => {
+            info!("Cache existed {:?}", cache_lookup);
+        }
+        _ => {}
+    }
+
+    Ok(response.bytes_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use seele_shared::entities::add_file::{FileItem, FileItemExt};
+    use tokio::fs::{self, File};
+
+    use crate::conf::LocalFileStrategy;
+
+    #[tokio::test]
+    async fn test_handle_inline() {
+        const PATH: &str = "./test-inline.txt";
+
+        let file = File::create(PATH).await.unwrap();
+        let text = "EXAMPLE 测试".to_string();
+
+        super::handle_plain_text(file, &text).await.unwrap();
+
+        assert_eq!(fs::read_to_string(PATH).await.unwrap(), text);
+
+        fs::remove_file(PATH).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_base64() {
+        const PATH: &str = "./test-base64.txt";
+
+        let file = File::create(PATH).await.unwrap();
+        let base64 = "5biM5YS/5pyA5Y+v54ix5LqG".to_string();
+
+        super::handle_base64(file, &base64).await.unwrap();
+
+        assert_eq!(fs::read_to_string(PATH).await.unwrap(), "希儿最可爱了");
+
+        fs::remove_file(PATH).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verify_digest() {
+        const PATH: &str = "./test-digest.txt";
+
+        let file = File::create(PATH).await.unwrap();
+        let digest = super::handle_plain_text(file, "114514").await.unwrap();
+        fs::remove_file(PATH).await.unwrap();
+
+        let mut item = FileItem {
+            path: "a".try_into().unwrap(),
+            extract: None,
+            sha256: Some("2d48f8de".to_owned()),
+            size: Some(6),
+            store: None,
+            ext: FileItemExt::PlainText { plain: "114514".to_owned() },
+        };
+        let path = Path::new(PATH);
+        assert!(super::verify_digest(&item, Some(digest.clone()), path).await.is_err());
+
+        item.sha256 = Some(digest.sha256.to_uppercase());
+        super::verify_digest(&item, Some(digest.clone()), path).await.unwrap();
+
+        item.size = Some(7);
+        assert!(super::verify_digest(&item, Some(digest), path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_http_url() {
+        const PATH: &str = "./test-url.txt";
+
+        let file = File::create(PATH).await.unwrap();
+        let (_trigger, listener) = triggered::trigger();
+        super::handle_http_url(listener, file, "https://httpbin.io/user-agent", None)
+            .await
+            .unwrap();
+
+        let ua = &super::conf::CONFIG.http.user_agent;
+        assert_eq!(
+            fs::read_to_string(PATH).await.unwrap(),
+            format!("{{\n  \"user-agent\": \"{ua}\"\n}}\n")
+        );
+
+        fs::remove_file(PATH).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_local_path() {
+        const SOURCE_PATH: &str = "./test-local-source.txt";
+        const TARGET_PATH: &str = "./test-local-target.txt";
+        const TEXT: &str = "希儿最可爱了test114514";
+
+        fs::write(SOURCE_PATH, TEXT).await.unwrap();
+
+        super::local::add_local_file(
+            Path::new(TARGET_PATH),
+            Path::new(SOURCE_PATH),
+            &[".".into()],
+            LocalFileStrategy::Copy,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(TARGET_PATH).await.unwrap(), TEXT);
+
+        fs::remove_file(TARGET_PATH).await.unwrap();
+        fs::remove_file(SOURCE_PATH).await.unwrap();
+    }
+}
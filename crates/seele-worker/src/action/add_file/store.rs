@@ -0,0 +1,217 @@
+use std::{
+    fs::{self, Permissions},
+    future::Future,
+    io::ErrorKind,
+    os::unix::prelude::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use seele_shared::entities::add_file::{FileItem, StoreKey};
+use tokio::task::spawn_blocking;
+use tracing::{info, warn};
+
+use crate::{
+    conf,
+    shared::cache::{self, CacheCounter, CacheStats, ManagedCache},
+};
+
+const STORE_NAME: &str = "seele-add-file-store";
+
+#[derive(Debug)]
+struct StoreEntry {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Files materialized once and shared by the submissions via hard links. The
+/// entries are removed from the disk when they are evicted.
+static STORE: LazyLock<moka::future::Cache<String, Arc<StoreEntry>>> = LazyLock::new(|| {
+    let config = &conf::CONFIG.worker.action.add_file;
+    moka::future::Cache::builder()
+        .name(STORE_NAME)
+        .weigher(|_, entry: &Arc<StoreEntry>| -> u32 { entry.size.try_into().unwrap_or(u32::MAX) })
+        .max_capacity(1024 * 1024 * config.store_size_mib)
+        .time_to_idle(Duration::from_secs(60 * 60 * config.cache_ttl_hour))
+        .eviction_listener(|key, entry: Arc<StoreEntry>, cause| {
+            info!("Removing the data store entry {key}: {cause:?}");
+            if let Err(err) = remove_entry(&entry.path) {
+                warn!(path = %entry.path.display(), "Error removing the data store entry: {err:#}");
+            }
+        })
+        .support_invalidation_closures()
+        .build()
+});
+
+static COUNTER: CacheCounter = CacheCounter::new();
+
+struct DataStore;
+
+impl ManagedCache for DataStore {
+    fn name(&self) -> &'static str {
+        STORE_NAME
+    }
+
+    fn stats(&self) -> CacheStats {
+        COUNTER.make_stats(STORE_NAME, STORE.entry_count(), STORE.weighted_size())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        STORE.iter().map(|(key, _)| key.to_string()).collect()
+    }
+
+    fn purge(&self, prefix: Option<&str>) -> u64 {
+        let Some(prefix) = prefix else {
+            let count = STORE.entry_count();
+            STORE.invalidate_all();
+            return count;
+        };
+
+        let prefix = prefix.to_owned();
+        let count = STORE.iter().filter(|(key, _)| key.starts_with(&prefix)).count();
+        _ = STORE.invalidate_entries_if(move |key, _| key.starts_with(&prefix));
+        count as u64
+    }
+}
+
+pub fn init() {
+    LazyLock::force(&STORE);
+    cache::register(Arc::new(DataStore));
+}
+
+/// The key of the item in the data store, or `None` if the item does not use
+/// the data store
+pub fn make_key(item: &FileItem) -> Result<Option<String>> {
+    let key = match &item.store {
+        None | Some(StoreKey::ContentHash(false)) => return Ok(None),
+        Some(StoreKey::ContentHash(true)) => match &item.sha256 {
+            None => bail!("The `sha256` is required to key the data store by the content hash"),
+            Some(sha256) => format!("sha256:{}", sha256.to_lowercase()),
+        },
+        Some(StoreKey::Named(name)) => format!("name:{name}"),
+    };
+
+    Ok(Some(match item.extract {
+        None => key,
+        Some(format) => format!("{key}:{format}"),
+    }))
+}
+
+/// Hard-link the data store entry to the target path, materializing the entry
+/// with `init` if it does not exist
+pub async fn link<F, Fut>(key: String, target: &Path, init: F) -> Result<()>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let entry = STORE
+        .entry(key)
+        .or_try_insert_with(async {
+            let path = conf::PATHS.store.join(nano_id::base62::<16>());
+            let result = async {
+                init(path.clone()).await?;
+                spawn_blocking({
+                    let path = path.clone();
+                    move || seal_entry(&path)
+                })
+                .await?
+            }
+            .await;
+
+            match result {
+                Ok(size) => Ok(Arc::new(StoreEntry { path, size })),
+                Err(err) => {
+                    _ = spawn_blocking(move || remove_entry(&path)).await;
+                    Err(err)
+                }
+            }
+        })
+        .await
+        .map_err(|err| anyhow!("{err:#}"))?;
+
+    COUNTER.record(!entry.is_fresh());
+
+    let source = entry.into_value();
+    let target = target.to_owned();
+    spawn_blocking(move || link_entry(&source.path, &target))
+        .await?
+        .context("Error linking the data store entry")
+}
+
+/// Make the files read-only so that the submissions cannot modify them via the
+/// hard links. The mode does not stop root, so the worker removes the links
+/// before writing to the paths. Returns the total size of the files.
+pub(super) fn seal_entry(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        return fs::read_dir(path)?
+            .map(|item| seal_entry(&item?.path()))
+            .try_fold(0, |total, size| size.map(|size| total + size));
+    }
+
+    if !metadata.is_file() {
+        bail!("Unsupported file type: {}", path.display());
+    }
+
+    let mode = if metadata.permissions().mode() & 0o111 != 0 { 0o555 } else { 0o444 };
+    fs::set_permissions(path, Permissions::from_mode(mode))?;
+    Ok(metadata.len())
+}
+
+pub(super) fn link_entry(source: &Path, target: &Path) -> Result<()> {
+    if source.is_dir() {
+        fs::create_dir_all(target).context("Error creating the directory")?;
+        for item in fs::read_dir(source)? {
+            let item = item?;
+            link_entry(&item.path(), &target.join(item.file_name()))?;
+        }
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).context("Error creating the directories")?;
+    }
+
+    _ = fs::remove_file(target);
+    match fs::hard_link(source, target) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            fs::copy(source, target).map(|_| ()).context("Error copying the file")
+        }
+        Err(err) => Err(err).context("Error creating the hard link"),
+    }
+}
+
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::MetadataExt, path::Path};
+
+    #[test]
+    fn test_seal_and_link_entry() {
+        const ROOT: &str = "./test-store";
+
+        let root = Path::new(ROOT);
+        fs::create_dir_all(root.join("entry/data")).unwrap();
+        fs::write(root.join("entry/data/1.in"), "114").unwrap();
+        fs::write(root.join("entry/data/1.out"), "514").unwrap();
+
+        let size = super::seal_entry(&root.join("entry")).unwrap();
+        super::link_entry(&root.join("entry"), &root.join("submission")).unwrap();
+
+        let source = fs::metadata(root.join("entry/data/1.in")).unwrap();
+        let target = fs::metadata(root.join("submission/data/1.in")).unwrap();
+        let content = fs::read_to_string(root.join("submission/data/1.out")).unwrap();
+        fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(size, 6);
+        assert_eq!(source.ino(), target.ino());
+        assert_eq!(target.mode() & 0o777, 0o444);
+        assert_eq!(content, "514");
+    }
+}
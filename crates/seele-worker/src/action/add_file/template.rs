@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+
+/// Render the placeholders like `{{ name }}` with the variables, or like
+/// `{{ tags.name }}` with the tags of the action task. Braces whose content is
+/// not a valid name, e.g. `{{ put(1, 2); }}` in Java, are kept as-is.
+pub fn render(
+    template: &str,
+    vars: &HashMap<String, String>,
+    tags: &HashMap<String, String>,
+) -> Result<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(begin) = rest.find("{{") {
+        let Some(length) = rest[begin + 2..].find("}}") else {
+            break;
+        };
+
+        let name = rest[begin + 2..begin + 2 + length].trim();
+        result.push_str(&rest[..begin]);
+        if is_valid_name(name) {
+            let value = match name.strip_prefix("tags.") {
+                Some(tag) => tags.get(tag),
+                None => vars.get(name),
+            };
+            match value {
+                None => bail!("Unknown template variable: {name}"),
+                Some(value) => result.push_str(value),
+            }
+        } else {
+            result.push_str(&rest[begin..begin + 4 + length]);
+        }
+        rest = &rest[begin + 4 + length..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::render;
+
+    #[test]
+    fn test_render() {
+        let vars = HashMap::from([("class".to_owned(), "Solution".to_owned())]);
+        let tags = HashMap::from([("problem".to_owned(), "1001".to_owned())]);
+
+        assert_eq!(
+            render(
+                "// {{tags.problem}}\nclass Main { {{ class }} s = new {{class}}() {{ init(); }} }",
+                &vars,
+                &tags
+            )
+            .unwrap(),
+            "// 1001\nclass Main { Solution s = new Solution() {{ init(); }} }"
+        );
+        assert_eq!(render("{{ class", &vars, &tags).unwrap(), "{{ class");
+        assert!(render("{{ name }}", &vars, &tags).is_err());
+        assert!(render("{{ tags.name }}", &vars, &tags).is_err());
+    }
+}
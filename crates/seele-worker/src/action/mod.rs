@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 pub mod add_file;
 pub mod noop;
@@ -7,4 +7,10 @@ pub mod run_container;
 #[derive(Debug)]
 pub struct ActionContext {
     pub submission_root: PathBuf,
+
+    /// Tags of the action task
+    pub tags: HashMap<String, String>,
+
+    /// Files uploaded along with the submission, keyed by their names
+    pub attachments: Arc<HashMap<String, PathBuf>>,
 }
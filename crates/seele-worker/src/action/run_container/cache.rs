@@ -1,31 +1,80 @@
 use std::{
+    fmt::Write,
     sync::{Arc, LazyLock},
     time::Duration,
 };
 
 use moka::sync::Cache;
 
-use crate::conf;
+use crate::{
+    conf,
+    shared::cache::{self, CacheCounter, CacheStats, ManagedCache},
+};
+
+const CACHE_NAME: &str = "seele-run-container";
 
 #[allow(clippy::type_complexity)]
 static CACHE: LazyLock<Cache<Box<[u8]>, Arc<[u8]>>> = LazyLock::new(|| {
     let config = &conf::CONFIG.worker.action.run_container;
     Cache::builder()
-        .name("seele-run-container")
+        .name(CACHE_NAME)
         .weigher(|_, value: &Arc<[u8]>| -> u32 { value.len().try_into().unwrap_or(u32::MAX) })
         .max_capacity(1024 * 1024 * config.cache_size_mib)
         .time_to_idle(Duration::from_secs(60 * 60 * config.cache_ttl_hour))
+        .support_invalidation_closures()
         .build()
 });
 
+static COUNTER: CacheCounter = CacheCounter::new();
+
+struct RunContainerCache;
+
+impl ManagedCache for RunContainerCache {
+    fn name(&self) -> &'static str {
+        CACHE_NAME
+    }
+
+    fn stats(&self) -> CacheStats {
+        COUNTER.make_stats(CACHE_NAME, CACHE.entry_count(), CACHE.weighted_size())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        CACHE.iter().map(|(key, _)| encode_key(&key)).collect()
+    }
+
+    fn purge(&self, prefix: Option<&str>) -> u64 {
+        let Some(prefix) = prefix else {
+            let count = CACHE.entry_count();
+            CACHE.invalidate_all();
+            return count;
+        };
+
+        let prefix = prefix.to_lowercase();
+        let count = CACHE.iter().filter(|(key, _)| encode_key(key).starts_with(&prefix)).count();
+        _ = CACHE.invalidate_entries_if(move |key, _| encode_key(key).starts_with(&prefix));
+        count as u64
+    }
+}
+
 pub fn init() {
     LazyLock::force(&CACHE);
+    cache::register(Arc::new(RunContainerCache));
 }
 
 pub fn get(key: &[u8]) -> Option<Arc<[u8]>> {
-    CACHE.get(key)
+    let value = CACHE.get(key);
+    COUNTER.record(value.is_some());
+    value
 }
 
 pub fn write(key: Box<[u8]>, value: Arc<[u8]>) {
     CACHE.insert(key, value)
 }
+
+/// The keys are sha256 hashes, so we show them as hex strings
+fn encode_key(key: &[u8]) -> String {
+    key.iter().fold(String::with_capacity(key.len() * 2), |mut str, byte| {
+        _ = write!(str, "{byte:02x}");
+        str
+    })
+}
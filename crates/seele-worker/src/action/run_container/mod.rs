@@ -10,16 +10,20 @@ use nix::{
     unistd::Pid,
 };
 use seele_shared::entities::run_container::{
-    Config,
-    runj::{ContainerExecutionReport, ContainerExecutionStatus, RunjConfig},
+    Config, SamplingConfig,
+    runj::{self, ContainerExecutionReport, ContainerExecutionStatus, RunjConfig},
 };
 use thread_local::ThreadLocal;
 use tokio::sync::oneshot;
 use tracing::{Span, info, info_span, warn};
 use triggered::Listener;
 
-use self::utils::{check_and_create_directories, cleanup_overlayfs, make_runj_config};
 pub use self::{idmap::*, image::prepare_image};
+use self::{
+    output::OutputStreams,
+    sampler::Sampler,
+    utils::{cleanup_overlayfs, make_runj_config, prepare_fd_paths},
+};
 use super::ActionContext;
 use crate::{
     cgroup, conf,
@@ -30,7 +34,9 @@ use crate::{
 pub mod cache;
 mod idmap;
 mod image;
+mod output;
 pub mod run_judge;
+mod sampler;
 mod utils;
 
 static RUNNER_THREAD_LOCAL: LazyLock<Arc<ThreadLocal<i64>>> = LazyLock::new(Arc::default);
@@ -39,14 +45,47 @@ pub async fn execute(
     abort: Listener,
     ctx: &ActionContext,
     config: &Config,
+) -> Result<ActionReportExt> {
+    execute_with_mounts(abort, ctx, config, vec![]).await
+}
+
+/// Execute the container with the extra mounts prepared by the worker, whose
+/// sources are trusted and not resolved in the submission root
+pub async fn execute_with_mounts(
+    abort: Listener,
+    ctx: &ActionContext,
+    config: &Config,
+    trusted_mounts: Vec<runj::MountConfig>,
 ) -> Result<ActionReportExt> {
     image::prepare_image(abort.clone(), config.image.clone())
         .await
         .context("Error preparing the container image")?;
 
-    let runj_config =
-        make_runj_config(ctx, config.clone()).await.context("Error converting the config")?;
-    check_and_create_directories(&runj_config).await?;
+    let mut runj_config = make_runj_config(ctx, config.clone(), trusted_mounts)
+        .await
+        .context("Error converting the config")?;
+    let result = async {
+        // Before the streams replace the redirected files with the fifos
+        prepare_fd_paths(&runj_config).await?;
+        let streams = OutputStreams::prepare(
+            config.capture.as_ref(),
+            &config.limits.output_limit_kib,
+            &ctx.submission_root,
+            &mut runj_config,
+        )
+        .context("Error preparing the output streams")?;
+        anyhow::Ok(streams)
+    }
+    .await;
+    let streams = match result {
+        Ok(streams) => streams,
+        Err(err) => {
+            if let Err(err) = cleanup_overlayfs(&runj_config.overlayfs) {
+                warn!("Error cleaning up overlayfs directories: {err:#}");
+            }
+            return Err(err);
+        }
+    };
 
     let report = runner::spawn_blocking({
         let local = RUNNER_THREAD_LOCAL.clone();
@@ -56,28 +95,53 @@ pub async fn execute(
             seele.image = %config.image,
             seele.command = %config.command,
         );
-        move || span.in_scope(move || execute_runj(abort, &local, runj_config))
+        let sampling = config.sampling.clone();
+        move || {
+            span.in_scope(move || {
+                execute_runj(abort, &local, runj_config, streams, sampling.as_ref())
+            })
+        }
     })
     .await??;
 
     Ok(match report.status {
         ContainerExecutionStatus::Normal => {
-            ActionReportExt::Success(ActionSuccessReportExt::RunContainer(report))
+            ActionReportExt::Success(ActionSuccessReportExt::RunContainer(Box::new(report)))
         }
         _ => {
             if matches!(report.status, ContainerExecutionStatus::Unknown) {
                 warn!("Unknown container execution status");
             }
 
-            ActionReportExt::Failure(ActionFailureReportExt::RunContainer(report))
+            ActionReportExt::Failure(ActionFailureReportExt::RunContainer(Box::new(report)))
         }
     })
 }
 
 fn execute_runj(
+    abort: Listener,
+    local: &ThreadLocal<i64>,
+    config: RunjConfig,
+    streams: OutputStreams,
+    sampling: Option<&SamplingConfig>,
+) -> Result<ContainerExecutionReport> {
+    let overlayfs = config.overlayfs.clone();
+    let result = run_runj(abort, local, config, streams, sampling);
+
+    // Also cleaned up when failed before runj started
+    if let Err(err) = cleanup_overlayfs(&overlayfs) {
+        warn!("Error cleaning up overlayfs directories: {err:#}");
+    }
+
+    result
+}
+
+fn run_runj(
     abort: Listener,
     local: &ThreadLocal<i64>,
     mut config: RunjConfig,
+    mut streams: OutputStreams,
+    sampling: Option<&SamplingConfig>,
 ) -> Result<ContainerExecutionReport> {
     {
         let cpu = match local.get() {
@@ -93,9 +157,20 @@ fn execute_runj(
         config.limits.cgroup.cpuset_cpus = Some(format!("{cpu}"));
     }
 
+    let sampler = match sampling {
+        None => None,
+        Some(sampling) => {
+            let sampler = Sampler::start(sampling).context("Error starting the sampler")?;
+            config.cgroup_path = sampler.cgroup_path().to_owned();
+            Some(sampler)
+        }
+    };
+
     let config_json =
         serde_json::to_string(&config).context("Error serializing the converted config")?;
 
+    streams.start().context("Error starting the output streams")?;
+
     let mut output = vec![];
     let mut reader = cmd!(&conf::CONFIG.paths.runj)
         .stdin_bytes(config_json.as_bytes())
@@ -122,10 +197,8 @@ fn execute_runj(
     });
 
     let result = reader.read_to_end(&mut output);
-
-    if let Err(err) = cleanup_overlayfs(&config.overlayfs) {
-        warn!("Error cleaning up overlayfs directories: {err:#}");
-    }
+    let output_report = streams.finish();
+    let timeline = sampler.and_then(Sampler::finish);
 
     _ = cancel_tx.send(());
     if abort.is_triggered() {
@@ -134,8 +207,14 @@ fn execute_runj(
 
     match result {
         Ok(_) => {
-            let report: ContainerExecutionReport =
+            let mut report: ContainerExecutionReport =
                 serde_json::from_slice(&output[..]).context("Error deserializing the report")?;
+            report.stdout = output_report.stdout;
+            report.stderr = output_report.stderr;
+            report.timeline = timeline;
+            if output_report.limit_exceeded {
+                report.status = ContainerExecutionStatus::OutputLimitExceeded;
+            }
             info!(
                 seele.container.status = %report.status,
                 seele.container.code = report.exit_code,
@@ -0,0 +1,530 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions, Permissions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Context, Result, bail};
+use nix::{
+    fcntl::{self, FcntlArg, OFlag},
+    sys::stat::Mode,
+    unistd,
+};
+use seele_shared::entities::run_container::{
+    CaptureConfig, CapturedOutput, OutputLimitConfig,
+    runj::{FdConfig, RunjConfig},
+};
+use tracing::warn;
+
+use crate::conf;
+
+/// The stdout and the stderr of a container which are captured or limited by
+/// the worker. The captured ones redirected to files without limits are read
+/// from the files after the container exits. The others are redirected to fifos
+/// in the temp directory, which are read while the container runs and copied to
+/// the redirected files if any. The redirected files are opened without
+/// following the symbolic links, since the submission may have replaced them.
+/// Once a stream exceeds its limit, the fifo is
+/// closed so that the container gets `SIGPIPE` on writing it.
+#[derive(Default)]
+pub struct OutputStreams {
+    stdout: Option<Stream>,
+    stderr: Option<Stream>,
+}
+
+#[derive(Debug, Default)]
+pub struct OutputReport {
+    pub stdout: Option<CapturedOutput>,
+    pub stderr: Option<CapturedOutput>,
+    pub limit_exceeded: bool,
+}
+
+impl OutputStreams {
+    /// Check the config before any directories of the container are created,
+    /// since the stream redirected to the other one cannot be read by itself
+    pub fn check(
+        capture: Option<&CaptureConfig>,
+        limits: &OutputLimitConfig,
+        fd: Option<&FdConfig>,
+    ) -> Result<()> {
+        let streams = [
+            (
+                "stdout",
+                fd.is_some_and(|fd| fd.stdout_to_stderr),
+                capture.is_some_and(|config| config.stdout) || limits.stdout.is_some(),
+            ),
+            (
+                "stderr",
+                fd.is_some_and(|fd| fd.stderr_to_stdout),
+                capture.is_some_and(|config| config.stderr) || limits.stderr.is_some(),
+            ),
+        ];
+        for (name, redirected, read) in streams {
+            if redirected && read {
+                bail!("Cannot capture or limit the {name} redirected to the other stream");
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepare the streams checked by [`Self::check`], redirecting them to
+    /// fifos if needed
+    pub fn prepare(
+        capture: Option<&CaptureConfig>,
+        limits: &OutputLimitConfig,
+        root: &Path,
+        runj_config: &mut RunjConfig,
+    ) -> Result<Self> {
+        let capture_limits = capture.map(|config| CaptureLimits {
+            head_bytes: (config.head_kib * 1024) as usize,
+            tail_bytes: (config.tail_kib * 1024) as usize,
+        });
+        let capture_stdout = capture_limits.filter(|_| capture.is_some_and(|config| config.stdout));
+        let capture_stderr = capture_limits.filter(|_| capture.is_some_and(|config| config.stderr));
+        let fd = runj_config.fd.get_or_insert_with(Default::default);
+
+        Ok(Self {
+            stdout: Stream::prepare(
+                "stdout",
+                root,
+                &mut fd.stdout,
+                capture_stdout,
+                limits.stdout.map(|kib| kib * 1024),
+            )?,
+            stderr: Stream::prepare(
+                "stderr",
+                root,
+                &mut fd.stderr,
+                capture_stderr,
+                limits.stderr.map(|kib| kib * 1024),
+            )?,
+        })
+    }
+
+    /// Start reading the fifos, which must happen before runj opens them
+    pub fn start(&mut self) -> Result<()> {
+        for stream in self.stdout.iter_mut().chain(self.stderr.iter_mut()) {
+            stream.start()?;
+        }
+        Ok(())
+    }
+
+    /// Collect the outputs after the container exits, the failed ones are
+    /// omitted
+    pub fn finish(self) -> OutputReport {
+        let mut report = OutputReport::default();
+        for (stream, output) in
+            [(self.stdout, &mut report.stdout), (self.stderr, &mut report.stderr)]
+        {
+            let Some(stream) = stream else {
+                continue;
+            };
+
+            let name = stream.name;
+            match stream.finish() {
+                Ok(stream_report) => {
+                    *output = stream_report.captured;
+                    report.limit_exceeded |= stream_report.limit_exceeded;
+                }
+                Err(err) => warn!("Error reading the {name}: {err:#}"),
+            }
+        }
+        report
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CaptureLimits {
+    head_bytes: usize,
+    tail_bytes: usize,
+}
+
+enum StreamKind {
+    /// Captured from the redirected file after the container exits
+    File { capture: CaptureLimits },
+
+    /// Read from the fifo while the container runs
+    Fifo { target: Option<PathBuf>, capture: Option<CaptureLimits>, limit_bytes: Option<u64> },
+}
+
+struct Stream {
+    name: &'static str,
+    path: PathBuf,
+    kind: StreamKind,
+
+    /// The submission root which the redirected file must stay in
+    root: PathBuf,
+
+    /// The write end held to keep the reader from seeing the end of file before
+    /// runj opens the fifo, along with the reader thread
+    reader: Option<(File, JoinHandle<io::Result<StreamReport>>)>,
+}
+
+struct StreamReport {
+    captured: Option<CapturedOutput>,
+    limit_exceeded: bool,
+}
+
+impl Stream {
+    fn prepare(
+        name: &'static str,
+        root: &Path,
+        target: &mut Option<PathBuf>,
+        capture: Option<CaptureLimits>,
+        limit_bytes: Option<u64>,
+    ) -> Result<Option<Self>> {
+        if capture.is_none() && limit_bytes.is_none() {
+            return Ok(None);
+        }
+
+        if let (Some(capture), None, Some(path)) = (capture, limit_bytes, &target) {
+            let kind = StreamKind::File { capture };
+            let root = root.to_owned();
+            return Ok(Some(Self { name, path: path.clone(), kind, root, reader: None }));
+        }
+
+        let mut stream = Self::fifo(&conf::PATHS.temp, name, root, None, capture, limit_bytes)?;
+        if let StreamKind::Fifo { target: stream_target, .. } = &mut stream.kind {
+            *stream_target = target.replace(stream.path.clone());
+        }
+        Ok(Some(stream))
+    }
+
+    fn fifo(
+        directory: &Path,
+        name: &'static str,
+        root: &Path,
+        target: Option<PathBuf>,
+        capture: Option<CaptureLimits>,
+        limit_bytes: Option<u64>,
+    ) -> Result<Self> {
+        let path = directory.join(format!("__run_container_{name}_{}", nano_id::base62::<8>()));
+        unistd::mkfifo(&path, Mode::S_IRUSR | Mode::S_IWUSR)
+            .with_context(|| format!("Error creating the fifo: {}", path.display()))?;
+        let kind = StreamKind::Fifo { target, capture, limit_bytes };
+        Ok(Self { name, path, kind, root: root.to_owned(), reader: None })
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let StreamKind::Fifo { target, capture, limit_bytes } = &self.kind else {
+            return Ok(());
+        };
+
+        // Opening the read end does not block with `O_NONBLOCK`, after which the
+        // write end can be opened without blocking either
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(OFlag::O_NONBLOCK.bits())
+            .open(&self.path)
+            .context("Error opening the read end of the fifo")?;
+        let writer = OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .context("Error opening the write end of the fifo")?;
+        fcntl::fcntl(&file, FcntlArg::F_SETFL(OFlag::empty()))
+            .context("Error making the fifo blocking")?;
+
+        let target = match target {
+            None => None,
+            Some(path) => Some(create_target(path, &self.root).with_context(|| {
+                format!("Error creating the redirected file: {}", path.display())
+            })?),
+        };
+
+        let (capture, limit_bytes) = (*capture, *limit_bytes);
+        let handle = thread::spawn(move || pump(file, target, capture, limit_bytes));
+        self.reader = Some((writer, handle));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<StreamReport> {
+        if let StreamKind::File { capture } = self.kind {
+            let captured = collect_file(&self.path, &self.root, capture)
+                .with_context(|| format!("Error reading the file: {}", self.path.display()))?;
+            return Ok(StreamReport { captured: Some(captured), limit_exceeded: false });
+        }
+
+        let Some((writer, handle)) = self.reader.take() else {
+            bail!("The stream was not started");
+        };
+        drop(writer);
+        match handle.join() {
+            Ok(result) => result.context("Error reading the fifo"),
+            Err(_) => bail!("The reader thread panicked"),
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        if matches!(self.kind, StreamKind::Fifo { .. }) {
+            _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Create the redirected file like runj does, whose parent directories have
+/// been created
+fn create_target(path: &Path, root: &Path) -> io::Result<File> {
+    // The file may be a hard link to a data store entry, which must not be
+    // truncated
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+
+    let file =
+        open_redirected(path, root, OpenOptions::new().write(true).create(true).truncate(true))?;
+    file.set_permissions(Permissions::from_mode(0o664))?;
+    Ok(file)
+}
+
+/// Open the redirected file in the root directory, refusing the symbolic links
+/// and the special files like fifos
+fn open_redirected(path: &Path, root: &Path, options: &mut OpenOptions) -> io::Result<File> {
+    let parent = fs::canonicalize(path.parent().unwrap_or(path))?;
+    if !parent.starts_with(fs::canonicalize(root)?) {
+        return Err(io::Error::other("The file escapes the root directory"));
+    }
+
+    let file = options.custom_flags((OFlag::O_NOFOLLOW | OFlag::O_NONBLOCK).bits()).open(path)?;
+    if !file.metadata()?.is_file() {
+        return Err(io::Error::other("The file is not a regular file"));
+    }
+    Ok(file)
+}
+
+/// Keeps the head and the tail of the output
+struct Collector {
+    limits: CaptureLimits,
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    size_bytes: u64,
+}
+
+impl Collector {
+    fn new(limits: CaptureLimits) -> Self {
+        Self { limits, head: vec![], tail: VecDeque::new(), size_bytes: 0 }
+    }
+
+    fn push(&mut self, mut data: &[u8]) {
+        self.size_bytes += data.len() as u64;
+
+        let count = (self.limits.head_bytes - self.head.len()).min(data.len());
+        self.head.extend_from_slice(&data[..count]);
+        data = &data[count..];
+
+        let tail_bytes = self.limits.tail_bytes;
+        if data.len() >= tail_bytes {
+            self.tail.clear();
+            self.tail.extend(&data[data.len() - tail_bytes..]);
+        } else {
+            let overflow = (self.tail.len() + data.len()).saturating_sub(tail_bytes);
+            self.tail.drain(..overflow);
+            self.tail.extend(data);
+        }
+    }
+
+    fn finish(mut self) -> CapturedOutput {
+        let truncated = self.size_bytes > (self.head.len() + self.tail.len()) as u64;
+        if !truncated {
+            self.head.extend(self.tail);
+            return CapturedOutput {
+                head: String::from_utf8_lossy(&self.head).into_owned(),
+                tail: None,
+                truncated,
+                size_bytes: self.size_bytes,
+            };
+        }
+
+        CapturedOutput {
+            head: String::from_utf8_lossy(trim_partial_end(&self.head)).into_owned(),
+            tail: Some(
+                String::from_utf8_lossy(trim_partial_start(self.tail.make_contiguous()))
+                    .into_owned(),
+            ),
+            truncated,
+            size_bytes: self.size_bytes,
+        }
+    }
+}
+
+/// Copy the fifo to the redirected file until the end of file or the limit is
+/// exceeded, after which the read end is closed
+fn pump(
+    mut reader: impl Read,
+    mut target: Option<File>,
+    capture: Option<CaptureLimits>,
+    limit_bytes: Option<u64>,
+) -> io::Result<StreamReport> {
+    let mut collector = capture.map(Collector::new);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size_bytes = 0;
+    let mut limit_exceeded = false;
+    loop {
+        let mut data = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => &buffer[..count],
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        if let Some(limit_bytes) = limit_bytes
+            && size_bytes + data.len() as u64 > limit_bytes
+        {
+            data = &data[..(limit_bytes - size_bytes) as usize];
+            limit_exceeded = true;
+        }
+
+        size_bytes += data.len() as u64;
+        if let Some(target) = &mut target {
+            target.write_all(data)?;
+        }
+        if let Some(collector) = &mut collector {
+            collector.push(data);
+        }
+
+        if limit_exceeded {
+            break;
+        }
+    }
+
+    Ok(StreamReport { captured: collector.map(Collector::finish), limit_exceeded })
+}
+
+/// Read only the head and the tail of the file, which might be large
+fn collect_file(path: &Path, root: &Path, limits: CaptureLimits) -> io::Result<CapturedOutput> {
+    let mut file = open_redirected(path, root, OpenOptions::new().read(true))?;
+    let size_bytes = file.metadata()?.len();
+
+    let mut data = vec![];
+    (&mut file).take(limits.head_bytes as u64).read_to_end(&mut data)?;
+    let tail_start = size_bytes.saturating_sub(limits.tail_bytes as u64).max(data.len() as u64);
+    file.seek(SeekFrom::Start(tail_start))?;
+
+    let mut collector = Collector::new(limits);
+    collector.push(&data);
+    data.clear();
+    file.take(limits.tail_bytes as u64).read_to_end(&mut data)?;
+    collector.push(&data);
+    collector.size_bytes = size_bytes;
+    Ok(collector.finish())
+}
+
+/// Remove the incomplete UTF-8 sequence at the end
+fn trim_partial_end(data: &[u8]) -> &[u8] {
+    for i in (data.len().saturating_sub(3)..data.len()).rev() {
+        let width = match data[i] {
+            byte if byte & 0xC0 == 0x80 => continue,
+            byte if byte >= 0xF0 => 4,
+            byte if byte >= 0xE0 => 3,
+            byte if byte >= 0xC0 => 2,
+            _ => 1,
+        };
+        return if i + width > data.len() { &data[..i] } else { data };
+    }
+    data
+}
+
+/// Remove the incomplete UTF-8 sequence at the start
+fn trim_partial_start(data: &[u8]) -> &[u8] {
+    let count = data.iter().take(3).take_while(|byte| **byte & 0xC0 == 0x80).count();
+    &data[count..]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, os::unix};
+
+    use seele_shared::entities::run_container::{CaptureConfig, OutputLimitConfig, runj::FdConfig};
+
+    use super::{CaptureLimits, OutputStreams, Stream, collect_file, pump};
+
+    const LIMITS: CaptureLimits = CaptureLimits { head_bytes: 4, tail_bytes: 4 };
+
+    #[test]
+    fn test_capture() {
+        let report = pump("abcdefgh".as_bytes(), None, Some(LIMITS), None).unwrap();
+        let output = report.captured.unwrap();
+        assert_eq!(output.head, "abcdefgh");
+        assert_eq!(output.tail, None);
+        assert!(!output.truncated);
+
+        // The characters cut by the limits are dropped
+        let report = pump("ab你好世界cd".as_bytes(), None, Some(LIMITS), None).unwrap();
+        let output = report.captured.unwrap();
+        assert_eq!(output.head, "ab");
+        assert_eq!(output.tail.as_deref(), Some("cd"));
+        assert!(output.truncated);
+        assert_eq!(output.size_bytes, 16);
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("output");
+        fs::write(&path, "a你好世界").unwrap();
+        let output = collect_file(&path, directory.path(), LIMITS).unwrap();
+        assert_eq!(output.head, "a你");
+        assert_eq!(output.tail.as_deref(), Some("界"));
+        assert_eq!(output.size_bytes, 13);
+
+        // The file replaced by a symbolic link is not followed
+        let outside = tempfile::NamedTempFile::new().unwrap();
+        fs::remove_file(&path).unwrap();
+        unix::fs::symlink(outside.path(), &path).unwrap();
+        assert!(collect_file(&path, directory.path(), LIMITS).is_err());
+
+        // Neither is the directory replaced by one
+        let nested = directory.path().join("nested");
+        unix::fs::symlink(outside.path().parent().unwrap(), &nested).unwrap();
+        let path = nested.join(outside.path().file_name().unwrap());
+        assert!(collect_file(&path, directory.path(), LIMITS).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let capture = CaptureConfig { stdout: true, stderr: false, head_kib: 4, tail_kib: 4 };
+        let limits = OutputLimitConfig { stdout: None, stderr: Some(64) };
+        let fd = |stdout_to_stderr, stderr_to_stdout| FdConfig {
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            stdout_to_stderr,
+            stderr_to_stdout,
+        };
+
+        assert!(OutputStreams::check(Some(&capture), &limits, None).is_ok());
+        assert!(OutputStreams::check(None, &Default::default(), Some(&fd(true, true))).is_ok());
+        assert!(OutputStreams::check(Some(&capture), &limits, Some(&fd(true, false))).is_err());
+        assert!(OutputStreams::check(None, &limits, Some(&fd(false, true))).is_err());
+    }
+
+    #[test]
+    fn test_stream_fifo() {
+        let directory = tempfile::tempdir().unwrap();
+        let target = directory.path().join("output");
+        let mut stream = Stream::fifo(
+            directory.path(),
+            "stdout",
+            directory.path(),
+            Some(target.clone()),
+            Some(LIMITS),
+            Some(8),
+        )
+        .unwrap();
+        let path = stream.path.clone();
+        stream.start().unwrap();
+
+        let mut writer = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        writer.write_all(b"foo\nbar\nbaz\n").unwrap();
+        drop(writer);
+
+        // Only the output within the limit is copied and captured
+        let report = stream.finish().unwrap();
+        assert!(report.limit_exceeded);
+        assert_eq!(report.captured.unwrap().head, "foo\nbar\n");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "foo\nbar\n");
+        assert!(!path.exists());
+    }
+}
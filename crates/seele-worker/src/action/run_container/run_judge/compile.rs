@@ -1,12 +1,15 @@
 use std::{
-    collections::HashMap, fs::Permissions, os::unix::prelude::PermissionsExt, path::Path, sync::Arc,
+    collections::HashMap,
+    fs::Permissions,
+    os::unix::prelude::{OsStrExt, PermissionsExt},
+    path::Path,
+    sync::Arc,
 };
 
 use anyhow::{Context, Result, bail};
 use seele_shared::entities::{
     ActionReportExt, ActionSuccessReportExt,
     run_container::{
-        self,
         run_judge::compile::{Config, ExecutionReport},
         runj,
     },
@@ -22,7 +25,7 @@ use tracing::{error, info, instrument, warn};
 use triggered::Listener;
 
 use super::DEFAULT_MOUNT_DIRECTORY;
-use crate::{ActionContext, run_container::cache};
+use crate::{ActionContext, run_container::cache, shared};
 
 type CacheData = HashMap<String, Box<[u8]>>;
 
@@ -50,17 +53,21 @@ pub async fn execute(
                 .context("Error deserializing the data")?;
 
                 for item in &config.saves {
-                    if !data.contains_key(item) {
+                    if !data.contains_key(&item.to_string()) {
                         bail!("No key found for {item}");
                     }
                 }
 
-                info!("Compilation cache hit, reusing files: {}", config.saves.join(", "));
+                info!(
+                    "Compilation cache hit, reusing files: {}",
+                    config.saves.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+                );
 
-                for (file, data) in data {
-                    let mut data = data.as_ref();
+                for item in &config.saves {
+                    let mut data = data[&item.to_string()].as_ref();
 
-                    let target = ctx.submission_root.join(file);
+                    let target = item.resolve(&ctx.submission_root).await?;
+                    shared::file::remove_existing_file(&target).await?;
                     let mut writer = BufWriter::new(
                         OpenOptions::new()
                             .create(true)
@@ -92,32 +99,30 @@ pub async fn execute(
     fs::set_permissions(&mount_directory, Permissions::from_mode(0o777)).await?;
 
     let result = async {
-        let run_container_config = {
-            let mut run_container_config = config.run_container_config.clone();
-            run_container_config.cwd = DEFAULT_MOUNT_DIRECTORY.to_owned();
-
-            run_container_config.mounts.push(run_container::MountConfig::Full(runj::MountConfig {
-                from: mount_directory.clone(),
-                to: DEFAULT_MOUNT_DIRECTORY.to_owned(),
+        let mut run_container_config = config.run_container_config.clone();
+        run_container_config.cwd = DEFAULT_MOUNT_DIRECTORY.to_owned();
+
+        let mut mounts = vec![runj::MountConfig {
+            from: mount_directory.clone(),
+            to: DEFAULT_MOUNT_DIRECTORY.to_owned(),
+            options: None,
+        }];
+
+        for file in &config.sources {
+            mounts.push(runj::MountConfig {
+                from: file
+                    .from_path
+                    .resolve(&ctx.submission_root)
+                    .await
+                    .with_context(|| format!("Illegal source {file}"))?,
+                to: DEFAULT_MOUNT_DIRECTORY.join(&file.to_path),
                 options: None,
-            }));
-
-            run_container_config.mounts.extend(
-                config
-                    .sources
-                    .iter()
-                    .map(|file| runj::MountConfig {
-                        from: ctx.submission_root.join(&file.from_path),
-                        to: DEFAULT_MOUNT_DIRECTORY.join(&file.to_path),
-                        options: None,
-                    })
-                    .map(run_container::MountConfig::Full),
-            );
-
-            run_container_config
-        };
+            });
+        }
 
-        let report = crate::run_container::execute(handle, ctx, &run_container_config).await?;
+        let report =
+            crate::run_container::execute_with_mounts(handle, ctx, &run_container_config, mounts)
+                .await?;
 
         if matches!(report, ActionReportExt::Success(_)) {
             let mut cache_data: CacheData = Default::default();
@@ -125,7 +130,7 @@ pub async fn execute(
 
             for file in &config.saves {
                 let source = mount_directory.join(file);
-                let target = ctx.submission_root.join(file);
+                let target = file.resolve(&ctx.submission_root).await?;
                 let metadata = fs::metadata(&source)
                     .await
                     .with_context(|| format!("The file {file} to save does not exist"))?;
@@ -134,6 +139,7 @@ pub async fn execute(
                     bail!("Unknown supported file type: {file}");
                 }
 
+                shared::file::remove_existing_file(&target).await?;
                 fs::copy(source, &target).await.context("Error copying the file")?;
 
                 if hash.is_none() || cache_skipped {
@@ -146,7 +152,7 @@ pub async fn execute(
                     continue;
                 }
 
-                let name = file.clone();
+                let name = file.to_string();
                 let mut file = File::open(&target).await.context("Error opening the file")?;
                 let mut data = Vec::with_capacity(metadata.len() as usize);
 
@@ -154,13 +160,13 @@ pub async fn execute(
                 cache_data.insert(name, data.into_boxed_slice());
             }
 
-            if let Some(hash) = hash {
-                if !cache_data.is_empty() {
-                    let data =
-                        spawn_blocking(move || rkyv::to_bytes::<rkyv::rancor::Error>(&cache_data))
-                            .await??;
-                    cache::write(hash, Arc::from(data.into_boxed_slice()));
-                }
+            if let Some(hash) = hash
+                && !cache_data.is_empty()
+            {
+                let data =
+                    spawn_blocking(move || rkyv::to_bytes::<rkyv::rancor::Error>(&cache_data))
+                        .await??;
+                cache::write(hash, Arc::from(data.into_boxed_slice()));
             }
         }
 
@@ -192,17 +198,17 @@ async fn calculate_hash(submission_root: &Path, config: &Config) -> Result<Box<[
     saves.sort();
 
     for item in saves {
-        hasher.update(item);
+        hasher.update(item.as_os_str().as_bytes());
     }
 
     let mut sources = config.sources.clone();
     sources.sort();
 
     for item in sources {
-        hasher.update(&item.from_path);
+        hasher.update(item.from_path.as_os_str().as_bytes());
         hasher.update(&item.to_path);
 
-        let mut file = File::open(submission_root.join(&item.from_path))
+        let mut file = File::open(item.from_path.resolve(submission_root).await?)
             .await
             .context("Error opening the file")?;
 
@@ -220,7 +226,9 @@ async fn calculate_hash(submission_root: &Path, config: &Config) -> Result<Box<[
 mod tests {
     use std::path::Path;
 
-    use seele_shared::entities::run_container::{CommandConfig, run_judge::compile::CacheConfig};
+    use seele_shared::entities::run_container::{
+        self, CommandConfig, run_judge::compile::CacheConfig,
+    };
     use tokio::fs;
 
     use super::*;
@@ -236,10 +244,13 @@ mod tests {
                 fd: None,
                 paths: None,
                 mounts: vec![],
+                network: Default::default(),
                 limits: Default::default(),
+                capture: None,
+                sampling: None,
             },
             sources: vec!["main.c".try_into().unwrap()],
-            saves: vec!["main".to_owned()],
+            saves: vec!["main".try_into().unwrap()],
             cache: CacheConfig {
                 enabled: false,
                 max_allowed_size_mib: 114,
@@ -1,9 +1,13 @@
-use std::{fs::Permissions, os::unix::prelude::PermissionsExt};
+use std::{
+    fs::Permissions,
+    os::unix::prelude::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result, bail};
 use seele_shared::entities::{
     ActionReportExt,
-    run_container::{self, run_judge::run::Config, runj},
+    run_container::{run_judge::run::Config, runj},
 };
 use tokio::fs;
 use tracing::{instrument, warn};
@@ -22,17 +26,18 @@ pub async fn execute(
     // XXX: 0o777 is mandatory. The group bit is for rootless case and the others
     // bit is for rootful case.
     fs::set_permissions(&mount_directory, Permissions::from_mode(0o777)).await?;
+    let mut copies = vec![];
 
     let result = async {
         let mut run_container_config = config.run_container_config.clone();
 
         run_container_config.cwd = DEFAULT_MOUNT_DIRECTORY.to_owned();
 
-        run_container_config.mounts.push(run_container::MountConfig::Full(runj::MountConfig {
+        let mut mounts = vec![runj::MountConfig {
             from: mount_directory.clone(),
             to: DEFAULT_MOUNT_DIRECTORY.to_owned(),
             options: None,
-        }));
+        }];
 
         if let Some(paths) = run_container_config.paths.as_mut() {
             paths.push(DEFAULT_MOUNT_DIRECTORY.to_owned());
@@ -41,30 +46,38 @@ pub async fn execute(
         }
 
         for file in &config.files {
-            let from_path = ctx.submission_root.join(&file.from_path);
-
-            if let Err(err) = fs::metadata(&from_path).await {
-                bail!("The file {file} does not exist: {err:#}");
-            }
-
-            run_container_config.mounts.push(run_container::MountConfig::Full({
-                if file.exec {
-                    fs::set_permissions(&from_path, Permissions::from_mode(0o777))
+            let from_path = file
+                .from_path
+                .resolve(&ctx.submission_root)
+                .await
+                .with_context(|| format!("Illegal file {file}"))?;
+
+            let metadata = match fs::metadata(&from_path).await {
+                Ok(metadata) => metadata,
+                Err(err) => bail!("The file {file} does not exist: {err:#}"),
+            };
+
+            mounts.push({
+                let from_path = if !file.exec || metadata.mode() & 0o111 == 0o111 {
+                    from_path
+                } else {
+                    let shared = metadata.nlink() > 1;
+                    make_executable(from_path, shared, &crate::conf::PATHS.temp, &mut copies)
                         .await
                         .with_context(|| {
                             format!("Error setting the permission of the executable {file}")
-                        })?;
-                }
+                        })?
+                };
 
                 let to_path = DEFAULT_MOUNT_DIRECTORY.join(&file.to_path);
 
                 let options = if file.exec { Some(vec!["exec".to_owned()]) } else { None };
 
                 runj::MountConfig { from: from_path, to: to_path, options }
-            }));
+            });
         }
 
-        crate::run_container::execute(handle, ctx, &run_container_config).await
+        crate::run_container::execute_with_mounts(handle, ctx, &run_container_config, mounts).await
     }
     .await;
 
@@ -72,5 +85,63 @@ pub async fn execute(
         warn!(directory = %mount_directory.display(), "Error removing mount directory: {err:#}")
     }
 
+    for path in copies {
+        if let Err(err) = fs::remove_file(&path).await {
+            warn!(path = %path.display(), "Error removing the executable copy: {err:#}")
+        }
+    }
+
     result
 }
+
+/// Make the file executable. The hard-linked files, e.g. the ones linked from
+/// the data store, share the inodes with others, so they are copied to the temp
+/// directory instead of being changed.
+async fn make_executable(
+    path: PathBuf,
+    shared: bool,
+    directory: &Path,
+    copies: &mut Vec<PathBuf>,
+) -> Result<PathBuf> {
+    let path = if shared {
+        let copy = directory.join(format!("__run_judge_exec_{}", nano_id::base62::<16>()));
+        copies.push(copy.clone());
+        fs::copy(&path, &copy).await.context("Error copying the file")?;
+        copy
+    } else {
+        path
+    };
+
+    fs::set_permissions(&path, Permissions::from_mode(0o777)).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::make_executable;
+
+    #[tokio::test]
+    async fn test_make_executable() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        let (source, linked) = (directory.join("source"), directory.join("linked"));
+        fs::write(&source, "114514").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o444)).unwrap();
+        fs::hard_link(&source, &linked).unwrap();
+
+        // The shared inode is left untouched
+        let mut copies = vec![];
+        let path = make_executable(linked.clone(), true, directory, &mut copies).await.unwrap();
+        assert_eq!(copies, std::slice::from_ref(&path));
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o777);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "114514");
+        assert_eq!(fs::metadata(&source).unwrap().permissions().mode() & 0o777, 0o444);
+
+        let path = make_executable(source.clone(), false, directory, &mut copies).await.unwrap();
+        assert_eq!(path, source);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(fs::metadata(&source).unwrap().permissions().mode() & 0o777, 0o777);
+    }
+}
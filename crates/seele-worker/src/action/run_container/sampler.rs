@@ -0,0 +1,164 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use seele_shared::entities::run_container::{ResourceTimeline, SamplingConfig};
+use tracing::warn;
+
+use crate::cgroup;
+
+const MIN_INTERVAL_MS: u64 = 10;
+
+/// Samples the resource usage of a container from the cgroup created for it,
+/// in which runj creates the cgroup of the container
+pub struct Sampler {
+    cgroup_path: PathBuf,
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<ResourceTimeline>>,
+}
+
+impl Sampler {
+    pub fn start(config: &SamplingConfig) -> Result<Self> {
+        let cgroup_path = cgroup::create_container_parent_cgroup(&format!(
+            "sampled-{}",
+            nano_id::base62::<12>()
+        ))?;
+
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::spawn({
+            let cgroup_path = cgroup_path.clone();
+            let mut timeline = Timeline::new(config);
+            move || {
+                let begin = Instant::now();
+                loop {
+                    if let Some((memory_kib, cpu_time_ms)) = sample(&cgroup_path) {
+                        timeline.push(begin.elapsed().as_millis() as u64, memory_kib, cpu_time_ms);
+                    }
+
+                    match stopped.recv_timeout(timeline.interval) {
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        _ => return timeline.samples,
+                    }
+                }
+            }
+        });
+
+        Ok(Self { cgroup_path, stop: Some(stop), handle: Some(handle) })
+    }
+
+    pub fn cgroup_path(&self) -> &Path {
+        &self.cgroup_path
+    }
+
+    /// Stop sampling after the container exits
+    pub fn finish(mut self) -> Option<ResourceTimeline> {
+        _ = self.stop.take()?.send(());
+        match self.handle.take()?.join() {
+            Ok(timeline) => Some(timeline),
+            Err(_) => {
+                warn!("The sampler thread panicked");
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        // The thread stops once the sender is dropped, while the cgroup can be
+        // removed only after runj removes the one of the container
+        drop(self.stop.take());
+        if let Err(err) = fs::remove_dir(&self.cgroup_path) {
+            warn!(path = %self.cgroup_path.display(), "Error removing the sampled cgroup: {err:#}");
+        }
+    }
+}
+
+struct Timeline {
+    interval: Duration,
+    max_samples: usize,
+    samples: ResourceTimeline,
+}
+
+impl Timeline {
+    fn new(config: &SamplingConfig) -> Self {
+        Self {
+            interval: Duration::from_millis(config.interval_ms.max(MIN_INTERVAL_MS)),
+            max_samples: config.max_samples.max(2),
+            samples: Default::default(),
+        }
+    }
+
+    fn push(&mut self, time_ms: u64, memory_kib: u64, cpu_time_ms: u64) {
+        if self.samples.time_ms.len() >= self.max_samples {
+            for column in [
+                &mut self.samples.time_ms,
+                &mut self.samples.memory_kib,
+                &mut self.samples.cpu_time_ms,
+            ] {
+                let mut i = 0;
+                column.retain(|_| {
+                    i += 1;
+                    i % 2 == 1
+                });
+            }
+            self.interval *= 2;
+        }
+
+        self.samples.time_ms.push(time_ms);
+        self.samples.memory_kib.push(memory_kib);
+        self.samples.cpu_time_ms.push(cpu_time_ms);
+    }
+}
+
+/// Read the memory usage and the CPU time of the cgroup
+fn sample(cgroup_path: &Path) -> Option<(u64, u64)> {
+    let memory_bytes =
+        fs::read_to_string(cgroup_path.join("memory.current")).ok()?.trim().parse::<u64>().ok()?;
+    let cpu_time_us = fs::read_to_string(cgroup_path.join("cpu.stat"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some((memory_bytes / 1024, cpu_time_us / 1000))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use seele_shared::entities::run_container::SamplingConfig;
+
+    use super::{Timeline, sample};
+
+    #[test]
+    fn test_timeline() {
+        let mut timeline = Timeline::new(&SamplingConfig { interval_ms: 10, max_samples: 4 });
+        for i in 0..5 {
+            timeline.push(i * 10, i, i);
+        }
+
+        // Every other sample is dropped when the samples are full
+        assert_eq!(timeline.samples.time_ms, [0, 20, 40]);
+        assert_eq!(timeline.samples.memory_kib, [0, 2, 4]);
+        assert_eq!(timeline.interval.as_millis(), 20);
+    }
+
+    #[test]
+    fn test_sample() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path();
+        assert_eq!(sample(path), None);
+
+        fs::write(path.join("memory.current"), "2097152\n").unwrap();
+        fs::write(path.join("cpu.stat"), "usage_usec 1500000\nuser_usec 1000000\n").unwrap();
+        assert_eq!(sample(path), Some((2048, 1500)));
+    }
+}
@@ -1,19 +1,57 @@
-use std::{fs::Permissions, os::unix::prelude::PermissionsExt};
+use std::{
+    fs::Permissions,
+    os::unix::prelude::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use futures_util::future;
 use nix::mount;
 use seele_shared::entities::run_container::runj::{OverlayfsConfig, RunjConfig};
 use tokio::fs;
 
-use super::{idmap, image};
+use super::{idmap, image, output::OutputStreams};
 use crate::{
     ActionContext, cgroup,
     conf::{self, SeeleWorkMode},
     entities::run_container::*,
-    shared,
+    shared::{self, path::SafePath},
 };
 
-pub async fn make_runj_config(ctx: &ActionContext, config: Config) -> Result<RunjConfig> {
+pub async fn make_runj_config(
+    ctx: &ActionContext,
+    config: Config,
+    trusted_mounts: Vec<runj::MountConfig>,
+) -> Result<RunjConfig> {
+    if !conf::CONFIG.worker.action.run_container.allowed_network_modes.contains(&config.network) {
+        bail!("The network mode is not allowed: {}", config.network);
+    }
+
+    OutputStreams::check(
+        config.capture.as_ref(),
+        &config.limits.output_limit_kib,
+        config.fd.as_ref(),
+    )?;
+
+    let command = config.command.try_into().context("Error parsing command")?;
+
+    let fd = match config.fd {
+        None => None,
+        Some(fd) => Some(runj::FdConfig {
+            stdin: resolve_fd_path(fd.stdin, &ctx.submission_root).await?,
+            stdout: resolve_fd_path(fd.stdout, &ctx.submission_root).await?,
+            stderr: resolve_fd_path(fd.stderr, &ctx.submission_root).await?,
+            ..fd
+        }),
+    };
+
+    let mut mounts = future::try_join_all(
+        config.mounts.into_iter().map(|item| item.into_runj_mount(&ctx.submission_root)),
+    )
+    .await
+    .context("Error parsing mount")?;
+    mounts.extend(trusted_mounts);
+
     let user_namespace = {
         match &conf::CONFIG.work_mode {
             SeeleWorkMode::Bare | SeeleWorkMode::BareSystemd | SeeleWorkMode::Containerized => {
@@ -32,6 +70,7 @@ pub async fn make_runj_config(ctx: &ActionContext, config: Config) -> Result<Run
         }
     };
 
+    // Created last, so that the invalid configs do not leave them behind
     let overlayfs = {
         let id = nano_id::base62::<8>();
 
@@ -52,22 +91,6 @@ pub async fn make_runj_config(ctx: &ActionContext, config: Config) -> Result<Run
         runj::OverlayfsConfig { lower_dir, upper_dir, work_dir, merged_dir }
     };
 
-    let command = config.command.try_into().context("Error parsing command")?;
-
-    let fd = config.fd.map(|fd| runj::FdConfig {
-        stdin: fd.stdin.map(|path| ctx.submission_root.join(path)),
-        stdout: fd.stdout.map(|path| ctx.submission_root.join(path)),
-        stderr: fd.stderr.map(|path| ctx.submission_root.join(path)),
-        ..fd
-    });
-
-    let mounts = config
-        .mounts
-        .into_iter()
-        .map(|item| item.into_runj_mount(&ctx.submission_root))
-        .collect::<Result<Vec<runj::MountConfig>, _>>()
-        .context("Error parsing mount")?;
-
     Ok(runj::RunjConfig {
         user_namespace,
         overlayfs,
@@ -77,11 +100,23 @@ pub async fn make_runj_config(ctx: &ActionContext, config: Config) -> Result<Run
         paths: config.paths,
         fd,
         mounts,
+        network: config.network,
         limits: config.limits.into(),
     })
 }
 
-pub async fn check_and_create_directories(config: &runj::RunjConfig) -> Result<()> {
+async fn resolve_fd_path(path: Option<PathBuf>, root: &Path) -> Result<Option<PathBuf>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let path = SafePath::try_from(path).context("Illegal fd path")?;
+    Ok(Some(path.resolve(root).await.context("Illegal fd path")?))
+}
+
+/// Create the parent directories of the redirected files, and remove the
+/// existing output files which runj would otherwise truncate
+pub async fn prepare_fd_paths(config: &runj::RunjConfig) -> Result<()> {
     if let Some(config) = &config.fd {
         if let Some(path) = &config.stdin {
             shared::file::create_parent_directories(path).await?;
@@ -89,10 +124,12 @@ pub async fn check_and_create_directories(config: &runj::RunjConfig) -> Result<(
 
         if let Some(path) = &config.stdout {
             shared::file::create_parent_directories(path).await?;
+            shared::file::remove_existing_file(path).await?;
         }
 
         if let Some(path) = &config.stderr {
             shared::file::create_parent_directories(path).await?;
+            shared::file::remove_existing_file(path).await?;
         }
     }
 
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use anyhow::{Result, bail};
 use chrono::Utc;
@@ -10,6 +10,7 @@ use seele_shared::{
     entities::{
         ActionFailedReport, ActionReport, ActionReportExt, ActionSuccessReport, ActionTaskConfig,
     },
+    runner::Scheduling,
 };
 use tokio::{
     sync::{mpsc, oneshot},
@@ -28,6 +29,9 @@ pub struct WorkerQueueItem {
     pub parent_span: Span,
     pub submission_id: String,
     pub submission_root: PathBuf,
+    pub scheduling: Scheduling,
+    pub tags: HashMap<String, String>,
+    pub attachments: Arc<HashMap<String, PathBuf>>,
     pub config: Arc<ActionTaskConfig>,
     pub report_tx: oneshot::Sender<Result<ActionReport>>,
 }
@@ -36,6 +40,7 @@ pub type WorkerQueueTx = mpsc::Sender<WorkerQueueItem>;
 pub type WorkerQueueRx = mpsc::Receiver<WorkerQueueItem>;
 
 pub async fn worker_bootstrap(handle: SubsystemHandle, tx: oneshot::Sender<bool>) -> Result<()> {
+    action::add_file::init();
     action::run_container::cache::init();
 
     let preload_images = &conf::CONFIG.worker.action.run_container.preload_images;
@@ -80,6 +85,9 @@ pub async fn worker_main(handle: SubsystemHandle, mut queue_rx: WorkerQueueRx) -
         trigger.trigger();
     });
 
+    // The items are spawned as soon as they arrive, so the queue never builds up
+    // and its order does not matter. The actions wait for the runners instead,
+    // which serve them by the tenants and the priorities.
     loop {
         let outer_handle = abort_handle.clone();
         tokio::select! {
@@ -91,7 +99,14 @@ pub async fn worker_main(handle: SubsystemHandle, mut queue_rx: WorkerQueueRx) -
                         let abort_handle = abort_handle.clone();
                         let span = info_span!(parent: item.parent_span, "worker_handle_submission");
                         async move {
-                            let report = execute_action(abort_handle.clone(), item.submission_root, &item.config).await;
+                            let ctx = ActionContext {
+                                submission_root: item.submission_root,
+                                tags: item.tags,
+                                attachments: item.attachments,
+                            };
+                            let report = shared::runner::SCHEDULING
+                                .scope(item.scheduling, execute_action(abort_handle.clone(), ctx, &item.config))
+                                .await;
 
                             if item.report_tx.send(report).is_err() {
                                 error!(submission_id = item.submission_id, "Error sending the report");
@@ -109,10 +124,10 @@ pub async fn worker_main(handle: SubsystemHandle, mut queue_rx: WorkerQueueRx) -
 
 async fn execute_action(
     handle: Listener,
-    submission_root: PathBuf,
+    ctx: ActionContext,
     task: &ActionTaskConfig,
 ) -> Result<ActionReport> {
-    let ctx = Arc::new(ActionContext { submission_root });
+    let ctx = Arc::new(ctx);
 
     let begin = Instant::now();
     let run_at = Utc::now();
@@ -0,0 +1,165 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Query},
+    http::{
+        StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use tokio::{io, net::TcpListener, sync::oneshot, task::spawn_blocking};
+use tokio_graceful_shutdown::SubsystemHandle;
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tracing::{error, info};
+
+use crate::{
+    composer, conf,
+    shared::cache::{self, CacheStats},
+};
+
+pub async fn admin_main(handle: SubsystemHandle) -> Result<()> {
+    if !conf::CONFIG.admin.enabled {
+        info!("Admin endpoint is disabled");
+        return Ok(());
+    }
+
+    let app = Router::new().nest("/admin", router());
+
+    let addr = SocketAddr::new(conf::CONFIG.admin.address, conf::CONFIG.admin.port);
+    let listener = TcpListener::bind(addr).await?;
+
+    info!("Running admin endpoint at: {addr}");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            handle.on_shutdown_requested().await;
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/caches", get(list_caches_handler))
+        .route("/caches/{name}", get(inspect_cache_handler).delete(purge_cache_handler))
+        .route("/submissions", get(list_submissions_handler))
+        .route(
+            "/submissions/{id}",
+            get(download_submission_handler).delete(remove_submission_handler),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct CacheQuery {
+    prefix: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheDetails {
+    #[serde(flatten)]
+    stats: CacheStats,
+    keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgeResult {
+    purged_count: u64,
+}
+
+async fn list_caches_handler() -> Json<Vec<CacheStats>> {
+    Json(cache::caches().iter().map(|cache| cache.stats()).collect())
+}
+
+async fn inspect_cache_handler(
+    Path(name): Path<String>,
+    Query(query): Query<CacheQuery>,
+) -> Response {
+    let Some(cache) = cache::find(&name) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown cache: {name}")).into_response();
+    };
+
+    let keys = cache
+        .keys()
+        .into_iter()
+        .filter(|key| query.prefix.as_ref().is_none_or(|prefix| key.starts_with(prefix)))
+        .collect();
+    Json(CacheDetails { stats: cache.stats(), keys }).into_response()
+}
+
+async fn purge_cache_handler(
+    Path(name): Path<String>,
+    Query(query): Query<CacheQuery>,
+) -> Response {
+    let Some(cache) = cache::find(&name) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown cache: {name}")).into_response();
+    };
+
+    let purged_count = cache.purge(query.prefix.as_deref());
+    Json(PurgeResult { purged_count }).into_response()
+}
+
+async fn list_submissions_handler() -> Response {
+    match composer::list_retained_submissions().await {
+        Ok(submissions) => Json(submissions).into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+/// Stream the retained directory as a tarball, whose entries are prefixed with
+/// the submission id. An error while archiving ends the body with the error, so
+/// that the client sees the broken transfer instead of a truncated tarball.
+async fn download_submission_handler(Path(id): Path<String>) -> Response {
+    let path = match composer::find_retained_submission(&id).await {
+        Err(err) => return internal_error(err),
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, format!("Unknown submission: {id}")).into_response();
+        }
+        Ok(Some(path)) => path,
+    };
+
+    let (reader, writer) = io::duplex(64 * 1024);
+    let writer = SyncIoBridge::new(writer);
+    let filename = format!("{id}.tar");
+    let (error_tx, error_rx) = oneshot::channel();
+    spawn_blocking(move || {
+        let mut builder = tar::Builder::new(writer);
+        // The symlinks created by the submission may point to the host files
+        builder.follow_symlinks(false);
+        if let Err(err) = builder.append_dir_all(&id, &path).and_then(|_| builder.finish()) {
+            error!("Error archiving the retained submission: {err:#}");
+            _ = error_tx.send(err);
+        }
+    });
+
+    let error = stream::once(error_rx).filter_map(|result| async move { result.ok().map(Err) });
+
+    (
+        [
+            (CONTENT_TYPE, "application/x-tar".to_owned()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        Body::from_stream(ReaderStream::new(reader).chain(error)),
+    )
+        .into_response()
+}
+
+async fn remove_submission_handler(Path(id): Path<String>) -> Response {
+    match composer::remove_retained_submission(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, format!("Unknown submission: {id}")).into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+fn internal_error(err: anyhow::Error) -> Response {
+    error!("Error handling the admin request: {err:#}");
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")).into_response()
+}
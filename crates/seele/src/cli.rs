@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use futures_util::StreamExt;
+use ring_channel::ring_channel;
+use seele_config::OciImage;
+use serde::Serialize;
+use tokio::{
+    fs,
+    sync::{mpsc, oneshot},
+    task::spawn_blocking,
+};
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing::*;
+
+use crate::{
+    cgroup, check_env,
+    composer::{self, ComposerQueueItem, DataFormat, DryRunReport, SubmissionSignalExt},
+    conf, worker,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A cloud-native online judge system")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the service with the exchanges, which is the default
+    Serve,
+
+    /// Print the JSON Schema of the submissions
+    Schema,
+
+    /// Validate and resolve the submission without running it
+    Validate {
+        /// Path of the submission, whose format is inferred from the extension
+        path: PathBuf,
+    },
+
+    /// Run the submission in-process and print its final signal
+    Run {
+        /// Path of the submission, whose format is inferred from the extension
+        path: PathBuf,
+
+        /// Attach a local file to the submission, e.g. `source=main.cpp`
+        #[arg(short, long = "attachment", value_name = "NAME=PATH", value_parser = parse_attachment)]
+        attachments: Vec<(String, PathBuf)>,
+
+        /// Print the progress signals as well
+        #[arg(long)]
+        progress: bool,
+    },
+
+    /// Pull the container image and unpack it into the image directory
+    PullImage { image: String },
+}
+
+fn parse_attachment(value: &str) -> Result<(String, PathBuf), String> {
+    match value.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_owned(), path.into()))
+        }
+        _ => Err(format!("Expected `NAME=PATH`, got `{value}`")),
+    }
+}
+
+pub fn print_schema() -> ExitCode {
+    print_json(&crate::shared::entities::submission_schema());
+    ExitCode::SUCCESS
+}
+
+pub fn validate(path: PathBuf) -> Result<ExitCode> {
+    let config = std::fs::read(&path)
+        .with_context(|| format!("Error reading the submission: {}", path.display()))?;
+
+    let report = composer::dry_run_submission(&config, format_of(&path));
+    print_json(&report);
+    Ok(match report {
+        DryRunReport::Resolved { .. } => ExitCode::SUCCESS,
+        DryRunReport::Invalid { .. } => ExitCode::FAILURE,
+    })
+}
+
+pub async fn run(
+    path: PathBuf,
+    attachments: Vec<(String, PathBuf)>,
+    progress: bool,
+) -> Result<ExitCode> {
+    setup_local().await?;
+
+    let config = fs::read(&path)
+        .await
+        .with_context(|| format!("Error reading the submission: {}", path.display()))?;
+    let format = format_of(&path);
+    let attachments = copy_attachments(attachments).await?;
+
+    let (completed_tx, completed_rx) = oneshot::channel();
+    run_toplevel(move |handle| async move {
+        let (bootstrap_tx, bootstrap_rx) = oneshot::channel();
+        handle.start(SubsystemBuilder::new("bootstrap", |handle| {
+            worker::worker_bootstrap(handle, bootstrap_tx)
+        }));
+        if !bootstrap_rx.await? {
+            bail!("Error bootstrapping the worker");
+        }
+
+        let (composer_queue_tx, composer_queue_rx) = mpsc::channel(1);
+        let (worker_queue_tx, worker_queue_rx) =
+            mpsc::channel(conf::CONFIG.thread_counts.runner * 4);
+        handle.start(SubsystemBuilder::new("composer", |handle| {
+            composer::composer_main(handle, composer_queue_rx, worker_queue_tx)
+        }));
+        handle.start(SubsystemBuilder::new("worker", |handle| {
+            worker::worker_main(handle, worker_queue_rx)
+        }));
+
+        let (status_tx, mut status_rx) = ring_channel(NonZeroUsize::MIN);
+        composer_queue_tx
+            .send(ComposerQueueItem { config, format, priority: None, attachments, status_tx })
+            .await?;
+
+        while let Some(signal) = status_rx.next().await {
+            match signal.ext {
+                SubmissionSignalExt::Progress(_) if !progress => continue,
+                SubmissionSignalExt::Progress(_) => print_json(&signal),
+                SubmissionSignalExt::Completed(_) | SubmissionSignalExt::Error(_) => {
+                    print_json(&signal);
+                    _ = completed_tx.send(matches!(signal.ext, SubmissionSignalExt::Completed(_)));
+                    break;
+                }
+            }
+        }
+
+        handle.request_shutdown();
+        Ok(())
+    })
+    .await?;
+
+    Ok(match completed_rx.await {
+        Ok(true) => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    })
+}
+
+pub async fn pull_image(image: String) -> Result<ExitCode> {
+    setup_local().await?;
+
+    let image = OciImage::from(image.as_str());
+    run_toplevel(move |handle| async move {
+        let (trigger, abort) = triggered::trigger();
+        let cancellation_token = handle.create_cancellation_token();
+        tokio::spawn(async move {
+            cancellation_token.cancelled().await;
+            trigger.trigger();
+        });
+
+        worker::action::run_container::prepare_image(abort, image.clone()).await?;
+        info!("Pulled the image: {image}");
+
+        handle.request_shutdown();
+        Ok(())
+    })
+    .await?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prepare the environment like the service does, except that the logs are
+/// written to stderr, leaving stdout to the signals
+async fn setup_local() -> Result<()> {
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::fmt()
+            .compact()
+            .with_line_number(true)
+            .with_max_level(conf::CONFIG.log_level)
+            .with_writer(std::io::stderr)
+            .finish(),
+    )
+    .context("Failed to initialize the tracing subscriber")?;
+
+    cgroup::setup_cgroup().await?;
+    spawn_blocking(check_env).await?
+}
+
+async fn run_toplevel<F, Fut>(subsystem: F) -> Result<()>
+where
+    F: FnOnce(SubsystemHandle) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("local", subsystem));
+    })
+    .catch_signals()
+    .handle_shutdown_requests(Duration::from_secs(10))
+    .await
+    .map_err(|err| {
+        for error in err.get_subsystem_errors() {
+            error!("{error:?}");
+        }
+        anyhow::anyhow!("Seele encountered fatal issue(s)")
+    })
+}
+
+/// Copy the files into the attachment directory, since the composer removes
+/// the attachments after the submission is handled
+async fn copy_attachments(attachments: Vec<(String, PathBuf)>) -> Result<HashMap<String, PathBuf>> {
+    let mut copies = HashMap::with_capacity(attachments.len());
+    for (name, path) in attachments {
+        if copies.contains_key(&name) {
+            bail!("Duplicate attachment: {name}");
+        }
+
+        let copy = conf::PATHS.attachments.join(nano_id::base62::<16>());
+        fs::copy(&path, &copy)
+            .await
+            .with_context(|| format!("Error copying the attachment: {}", path.display()))?;
+        copies.insert(name, copy);
+    }
+
+    Ok(copies)
+}
+
+fn format_of(path: &Path) -> DataFormat {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => DataFormat::Json,
+        Some("msgpack") => DataFormat::MessagePack,
+        _ => DataFormat::Yaml,
+    }
+}
+
+fn print_json(value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{json}"),
+        Err(err) => error!("Error serializing the value: {err:#}"),
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use nix::mount::{self, MntFlags};
+use seele_cgroup::CGROUP_CONTAINER_SLICE_PATH;
+use tracing::*;
+
+use crate::conf;
+
+/// Counts of the leftovers cleaned up by the janitor
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Leftovers {
+    mounts: usize,
+    directories: usize,
+    files: usize,
+    cgroups: usize,
+}
+
+/// Clean up what the containers and the image pulls of the last run left behind
+/// if it crashed, i.e. the overlayfs mounts and the directories of the
+/// containers, the log files of skopeo and umoci, and the cgroups of the
+/// containers. It should run before the cgroup subtrees are initialized.
+pub fn clean_up_leftovers() -> Result<()> {
+    let mut leftovers = Leftovers::default();
+
+    let busy_mounts = unmount_stale_overlays(&mut leftovers)?;
+    remove_temp_entries(&busy_mounts, &mut leftovers)?;
+    remove_stale_cgroups(&mut leftovers)?;
+
+    if leftovers == Leftovers::default() {
+        debug!("No leftovers of the last run found");
+    } else {
+        info!(
+            "Cleaned up the leftovers of the last run: {} mounts, {} directories and {} files in \
+             {}, {} cgroups in {}",
+            leftovers.mounts,
+            leftovers.directories,
+            leftovers.files,
+            conf::PATHS.temp.display(),
+            leftovers.cgroups,
+            CGROUP_CONTAINER_SLICE_PATH.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Unmount the mounts inside the temp directory, the deepest first, returning
+/// the ones failed to be unmounted
+fn unmount_stale_overlays(leftovers: &mut Leftovers) -> Result<Vec<PathBuf>> {
+    let Ok(temp) = fs::canonicalize(&conf::PATHS.temp) else {
+        return Ok(vec![]);
+    };
+
+    let content =
+        fs::read_to_string("/proc/self/mountinfo").context("Error reading the mount info")?;
+    let mut mount_points = content
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .map(|mount_point| PathBuf::from(unescape_mount_point(mount_point)))
+        .filter(|mount_point| mount_point.starts_with(&temp) && *mount_point != temp)
+        .collect::<Vec<_>>();
+    mount_points.sort_by_key(|mount_point| std::cmp::Reverse(mount_point.components().count()));
+    mount_points.dedup();
+
+    let mut busy_mounts = vec![];
+    for mount_point in mount_points {
+        match mount::umount2(&mount_point, MntFlags::MNT_DETACH) {
+            Ok(_) => {
+                debug!(path = %mount_point.display(), "Unmounted the stale mount");
+                leftovers.mounts += 1;
+            }
+            Err(err) => {
+                warn!(path = %mount_point.display(), "Error unmounting the stale mount: {err:#}");
+                busy_mounts.push(mount_point);
+            }
+        }
+    }
+
+    Ok(busy_mounts)
+}
+
+/// Mount points in the mount info escape the space, tab, newline and backslash
+/// characters as octal sequences
+fn unescape_mount_point(mount_point: &str) -> String {
+    let mut result = String::with_capacity(mount_point.len());
+    let mut rest = mount_point;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        match rest.get(1..4).and_then(|octal| u8::from_str_radix(octal, 8).ok()) {
+            Some(byte) => {
+                result.push(byte as char);
+                rest = &rest[4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Everything in the temp directory belongs to the actions of the last run,
+/// except the entries still holding the mounts failed to be unmounted
+fn remove_temp_entries(busy_mounts: &[PathBuf], leftovers: &mut Leftovers) -> Result<()> {
+    let temp = &conf::PATHS.temp;
+    if !temp.exists() {
+        return Ok(());
+    }
+
+    let canonical_temp = fs::canonicalize(temp).unwrap_or_else(|_| temp.clone());
+    for entry in fs::read_dir(temp).context("Error reading the temp directory")? {
+        let entry = entry?;
+        let path = entry.path();
+        let canonical_path = canonical_temp.join(entry.file_name());
+        if busy_mounts.iter().any(|mount_point| mount_point.starts_with(&canonical_path)) {
+            warn!(path = %path.display(), "Skipped the temp entry holding a busy mount");
+            continue;
+        }
+
+        let result = if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(&path).map(|_| leftovers.directories += 1)
+        } else {
+            fs::remove_file(&path).map(|_| leftovers.files += 1)
+        };
+        if let Err(err) = result {
+            warn!(path = %path.display(), "Error removing the temp entry: {err:#}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Kill the processes left in the container slice, and remove it along with its
+/// descendants, which is created again when the cgroup subtrees are initialized
+fn remove_stale_cgroups(leftovers: &mut Leftovers) -> Result<()> {
+    let slice = CGROUP_CONTAINER_SLICE_PATH.as_path();
+    if !slice.exists() {
+        return Ok(());
+    }
+
+    if let Err(err) = fs::write(slice.join("cgroup.kill"), "1") {
+        warn!(path = %slice.display(), "Error killing the processes in the stale cgroups: {err:#}");
+    }
+
+    remove_cgroup(slice, leftovers)
+}
+
+fn remove_cgroup(path: &Path, leftovers: &mut Leftovers) -> Result<()> {
+    for entry in fs::read_dir(path).context("Error reading the cgroup directory")? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_cgroup(&entry.path(), leftovers)?;
+        }
+    }
+
+    // The killed processes exit asynchronously, before which the cgroup is busy
+    let mut retries = 10;
+    loop {
+        match fs::remove_dir(path) {
+            Ok(_) => {
+                leftovers.cgroups += 1;
+                return Ok(());
+            }
+            Err(err) if err.kind() == io::ErrorKind::ResourceBusy && retries > 0 => {
+                retries -= 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Error removing the stale cgroup: {}", path.display())
+                });
+            }
+        }
+    }
+}
@@ -1,15 +1,20 @@
 #![allow(dead_code)]
 
-use std::{fs::create_dir_all, time::Duration};
+use std::{
+    fs::{create_dir_all, remove_dir_all},
+    process::ExitCode,
+    time::Duration,
+};
 
 use anyhow::{Context, Result, bail};
+use clap::Parser;
 use seele_composer as composer;
 use seele_config as conf;
 use seele_exchange as exchange;
 use seele_shared as shared;
 use seele_worker as worker;
 use tokio::{
-    runtime,
+    runtime::{self, Runtime},
     sync::{mpsc, oneshot},
     task::spawn_blocking,
     time::sleep,
@@ -19,27 +24,60 @@ use tokio_graceful_shutdown::{
 };
 use tracing::*;
 
-use crate::{conf::SeeleWorkMode, worker::action};
+use crate::{
+    cli::{Cli, Command},
+    conf::SeeleWorkMode,
+    worker::action,
+};
 
+mod admin;
 mod cgroup;
+mod cli;
 mod healthz;
+mod janitor;
 mod telemetry;
 
-fn main() {
-    let runtime = runtime::Builder::new_current_thread()
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        None | Some(Command::Serve) => {
+            serve();
+            return ExitCode::SUCCESS;
+        }
+        // Prints the schema without loading the configuration
+        Some(Command::Schema) => Ok(cli::print_schema()),
+        Some(Command::Validate { path }) => cli::validate(path),
+        Some(Command::Run { path, attachments, progress }) => {
+            build_runtime().block_on(cli::run(path, attachments, progress))
+        }
+        Some(Command::PullImage { image }) => build_runtime().block_on(cli::pull_image(image)),
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("Error: {err:#}");
+        ExitCode::FAILURE
+    })
+}
+
+fn build_runtime() -> Runtime {
+    runtime::Builder::new_current_thread()
         .max_blocking_threads(conf::CONFIG.thread_counts.worker + conf::CONFIG.thread_counts.runner)
         .thread_keep_alive(Duration::from_secs(u64::MAX))
         .enable_all()
         .build()
-        .expect("Error building tokio runtime");
+        .expect("Error building tokio runtime")
+}
 
-    runtime
+fn serve() {
+    build_runtime()
         .block_on(async move {
             telemetry::setup_telemetry().await?;
 
+            spawn_blocking(janitor::clean_up_leftovers).await??;
             cgroup::setup_cgroup().await?;
 
+            spawn_blocking(remove_stale_files).await??;
             spawn_blocking(check_env).await??;
+            composer::recover_submissions().await?;
 
             let result = Toplevel::new(move |s| async move {
                 s.start(SubsystemBuilder::new("seele", seele));
@@ -92,10 +130,12 @@ async fn seele(handle: SubsystemHandle) -> Result<()> {
 
     info!("Initializing seele components");
 
-    let (composer_queue_tx, composer_queue_rx) = mpsc::channel(conf::CONFIG.thread_counts.runner);
+    // The composer queues the submissions itself to admit them by their priorities
+    let (composer_queue_tx, composer_queue_rx) = mpsc::channel(1);
     let (worker_queue_tx, worker_queue_rx) = mpsc::channel(conf::CONFIG.thread_counts.runner * 4);
 
     handle.start(SubsystemBuilder::new("healthz", healthz::healthz_main));
+    handle.start(SubsystemBuilder::new("admin", admin::admin_main));
 
     handle.start(SubsystemBuilder::new("exchange", |handle| {
         exchange::exchange_main(handle, composer_queue_tx)
@@ -113,6 +153,24 @@ async fn seele(handle: SubsystemHandle) -> Result<()> {
     anyhow::Ok(())
 }
 
+/// Only done by the service, since the local commands may run alongside it
+fn remove_stale_files() -> Result<()> {
+    // The data store is indexed in memory, so the entries left by the last run are
+    // unreachable
+    if conf::PATHS.store.exists() {
+        info!("Removing the stale data store in {}", conf::PATHS.store.display());
+        remove_dir_all(&conf::PATHS.store).context("Error removing the stale data store")?;
+    }
+
+    // So are the attachments of the submissions received by the last run
+    if conf::PATHS.attachments.exists() {
+        info!("Removing the stale attachments in {}", conf::PATHS.attachments.display());
+        remove_dir_all(&conf::PATHS.attachments).context("Error removing the stale attachments")?;
+    }
+
+    Ok(())
+}
+
 fn check_env() -> Result<()> {
     debug!("Checking cpu counts");
     let logical_cpu_count = num_cpus::get();
@@ -152,7 +210,15 @@ fn check_env() -> Result<()> {
     }
 
     info!("Creating necessary directories in {}", conf::PATHS.root.display());
-    for path in [&conf::PATHS.images, &conf::PATHS.submissions, &conf::PATHS.temp] {
+    for path in [
+        &conf::PATHS.images,
+        &conf::PATHS.submissions,
+        &conf::PATHS.temp,
+        &conf::PATHS.store,
+        &conf::PATHS.attachments,
+        &conf::PATHS.journal,
+        &conf::PATHS.retained,
+    ] {
         create_dir_all(path)
             .with_context(|| format!("Error creating the directory: {}", path.display()))?;
     }
@@ -1,5 +1,10 @@
 package entities
 
+const (
+	NETWORK_MODE_NONE     = "none"
+	NETWORK_MODE_LOOPBACK = "loopback"
+)
+
 type RunjConfig struct {
 	UserNamespace *UserNamespaceConfig `mapstructure:"user_namespace"`
 	Overlayfs     *OverlayfsConfig     `mapstructure:"overlayfs" validate:"required"`
@@ -9,6 +14,7 @@ type RunjConfig struct {
 	Paths         []string             `mapstructure:"paths" validate:"dive,required"`
 	Fd            *FdConfig            `mapstructure:"fd"`
 	Mounts        []*MountConfig       `mapstructure:"mounts"`
+	Network       string               `mapstructure:"network" validate:"omitempty,oneof=none loopback"`
 	Limits        *LimitsConfig        `mapstructure:"limits" validate:"required"`
 }
 
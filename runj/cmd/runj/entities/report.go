@@ -8,4 +8,15 @@ type ExecutionReport struct {
 	CpuUserTimeMs   uint64 `json:"cpu_user_time_ms"`
 	CpuKernelTimeMs uint64 `json:"cpu_kernel_time_ms"`
 	MemoryUsageKiB  uint64 `json:"memory_usage_kib"`
+
+	// Optional statistics which are omitted if the kernel does not provide them
+	OomCount                   *uint64 `json:"oom_count,omitempty"`
+	OomKillCount               *uint64 `json:"oom_kill_count,omitempty"`
+	PidsPeak                   *uint64 `json:"pids_peak,omitempty"`
+	IoReadBytes                *uint64 `json:"io_read_bytes,omitempty"`
+	IoWriteBytes               *uint64 `json:"io_write_bytes,omitempty"`
+	CpuPressureSomeUs          *uint64 `json:"cpu_pressure_some_us,omitempty"`
+	CpuPressureFullUs          *uint64 `json:"cpu_pressure_full_us,omitempty"`
+	VoluntaryContextSwitches   *uint64 `json:"voluntary_context_switches,omitempty"`
+	InvoluntaryContextSwitches *uint64 `json:"involuntary_context_switches,omitempty"`
 }
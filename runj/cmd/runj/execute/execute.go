@@ -69,6 +69,12 @@ func Execute(ctx context.Context, config *entities.RunjConfig) (*entities.Execut
 		return nil, fmt.Errorf("Error creating libcontainer config: %w", err)
 	}
 
+	// libcontainer brings up the loopback interface in the new network namespace,
+	// which is only wanted in the loopback mode
+	if config.Network != entities.NETWORK_MODE_LOOPBACK {
+		containerConfig.Networks = nil
+	}
+
 	// This is mandatory for libcontainer to correctly handle cgroup path
 	containerConfig.Cgroups.Path = strings.Replace(cgroupPath, fs2.UnifiedMountpoint, "", 1)
 
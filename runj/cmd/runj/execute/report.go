@@ -8,6 +8,7 @@ import (
 
 	"github.com/darkyzhou/seele/runj/cmd/runj/entities"
 	"github.com/opencontainers/runc/libcontainer"
+	"github.com/samber/lo"
 	"github.com/sirupsen/logrus"
 	"golang.org/x/sys/unix"
 )
@@ -35,12 +36,14 @@ type ExecutionReportProps struct {
 
 func makeExecutionReport(props *ExecutionReportProps) (*entities.ExecutionReport, error) {
 	var (
-		memoryUsageKib uint64
-		cpuKernelMs    uint64
-		cpuUserMs      uint64
-		exitStatus     = STATUS_UNKNOWN
-		code           = -1
-		signal         = ""
+		memoryUsageKib             uint64
+		cpuKernelMs                uint64
+		cpuUserMs                  uint64
+		voluntaryContextSwitches   *uint64
+		involuntaryContextSwitches *uint64
+		exitStatus                 = STATUS_UNKNOWN
+		code                       = -1
+		signal                     = ""
 	)
 
 	// Since `process.Wait()` could return an error, both `state` and `stats` may be nil
@@ -58,6 +61,11 @@ func makeExecutionReport(props *ExecutionReportProps) (*entities.ExecutionReport
 		if ok && rusage.Maxrss > 0 {
 			memoryUsageKib = uint64(rusage.Maxrss)
 		}
+
+		if ok {
+			voluntaryContextSwitches = lo.ToPtr(uint64(rusage.Nvcsw))
+			involuntaryContextSwitches = lo.ToPtr(uint64(rusage.Nivcsw))
+		}
 	}
 
 	{
@@ -155,13 +163,17 @@ func makeExecutionReport(props *ExecutionReportProps) (*entities.ExecutionReport
 		exitStatus = STATUS_MEMORY_LIMIT_EXCEEDED
 	}
 
-	return &entities.ExecutionReport{
-		Status:          exitStatus,
-		ExitCode:        code,
-		Signal:          signal,
-		WallTimeMs:      uint64(props.wallTime.Milliseconds()),
-		CpuUserTimeMs:   cpuUserMs,
-		CpuKernelTimeMs: cpuKernelMs,
-		MemoryUsageKiB:  memoryUsageKib,
-	}, nil
+	report := &entities.ExecutionReport{
+		Status:                     exitStatus,
+		ExitCode:                   code,
+		Signal:                     signal,
+		WallTimeMs:                 uint64(props.wallTime.Milliseconds()),
+		CpuUserTimeMs:              cpuUserMs,
+		CpuKernelTimeMs:            cpuKernelMs,
+		MemoryUsageKiB:             memoryUsageKib,
+		VoluntaryContextSwitches:   voluntaryContextSwitches,
+		InvoluntaryContextSwitches: involuntaryContextSwitches,
+	}
+	readCgroupStats(props.cgroupPath, report)
+	return report, nil
 }
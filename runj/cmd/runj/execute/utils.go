@@ -2,6 +2,7 @@ package execute
 
 import (
 	"encoding/json"
+	"errors"
 	"fmt"
 	"os"
 	"strconv"
@@ -15,6 +16,7 @@ import (
 	"github.com/opencontainers/runc/libcontainer/cgroups/fs2"
 	"github.com/opencontainers/runtime-spec/specs-go"
 	"github.com/samber/lo"
+	"github.com/sirupsen/logrus"
 	"golang.org/x/sys/unix"
 )
 
@@ -203,13 +205,110 @@ func prepareOverlayfs(userNamespaceConfig *entities.UserNamespaceConfig, config
 }
 
 func checkIsOOM(cgroupPath string) (bool, error) {
-	memoryEvents, err := cgroups.ReadFile(cgroupPath, "memory.events")
+	memoryEvents, err := readKeyedFile(cgroupPath, "memory.events")
 	if err != nil {
 		return false, fmt.Errorf("Error reading memory events: %w", err)
 	}
-	index := strings.LastIndex(memoryEvents, "oom_kill")
-	// TODO: should handle the case when index+9 is out of bounds
-	return index > 0 && memoryEvents[index+9] != '0', nil
+	return memoryEvents["oom_kill"] > 0, nil
+}
+
+// Reads the optional statistics from the cgroup files, leaving the missing ones
+// as nil since some of the files are introduced by the recent kernels
+func readCgroupStats(cgroupPath string, report *entities.ExecutionReport) {
+	if memoryEvents, err := readKeyedFile(cgroupPath, "memory.events"); err == nil {
+		report.OomCount = lo.ToPtr(memoryEvents["oom"])
+		report.OomKillCount = lo.ToPtr(memoryEvents["oom_kill"])
+	} else {
+		logrus.Warnf("Error reading memory.events: %s", err)
+	}
+
+	if data, err := cgroups.ReadFile(cgroupPath, "pids.peak"); err == nil {
+		if peak, err := strconv.ParseUint(strings.TrimSpace(data), 10, 64); err == nil {
+			report.PidsPeak = &peak
+		}
+	} else {
+		logOptionalFileError("pids.peak", err)
+	}
+
+	// Each line is like `8:0 rbytes=1459200 wbytes=314773504 rios=192 wios=353 ...`
+	if data, err := cgroups.ReadFile(cgroupPath, "io.stat"); err == nil {
+		var readBytes, writeBytes uint64
+		for _, line := range strings.Split(data, "\n") {
+			fields := parseNestedKeys(line)
+			readBytes += fields["rbytes"]
+			writeBytes += fields["wbytes"]
+		}
+		report.IoReadBytes = &readBytes
+		report.IoWriteBytes = &writeBytes
+	} else {
+		logrus.Warnf("Error reading io.stat: %s", err)
+	}
+
+	// Each line is like `some avg10=0.00 avg60=0.00 avg300=0.00 total=1234`
+	if data, err := cgroups.ReadFile(cgroupPath, "cpu.pressure"); err == nil {
+		for _, line := range strings.Split(data, "\n") {
+			total, ok := parseNestedKeys(line)["total"]
+			if !ok {
+				continue
+			}
+			switch {
+			case strings.HasPrefix(line, "some "):
+				report.CpuPressureSomeUs = lo.ToPtr(total)
+			case strings.HasPrefix(line, "full "):
+				report.CpuPressureFullUs = lo.ToPtr(total)
+			}
+		}
+	} else {
+		logOptionalFileError("cpu.pressure", err)
+	}
+}
+
+// The optional files are missing before Linux 6.1 or without PSI enabled, which is
+// not worth a warning on every execution
+func logOptionalFileError(file string, err error) {
+	if errors.Is(err, os.ErrNotExist) {
+		logrus.Debugf("Skipped reading %s: %s", file, err)
+	} else {
+		logrus.Warnf("Error reading %s: %s", file, err)
+	}
+}
+
+// Parses the flat keyed files like `memory.events`, whose lines are like `oom_kill 0`
+func readKeyedFile(cgroupPath string, file string) (map[string]uint64, error) {
+	data, err := cgroups.ReadFile(cgroupPath, file)
+	if err != nil {
+		return nil, err
+	}
+
+	values := make(map[string]uint64)
+	for _, line := range strings.Split(data, "\n") {
+		fields := strings.Fields(line)
+		if len(fields) != 2 {
+			continue
+		}
+		value, err := strconv.ParseUint(fields[1], 10, 64)
+		if err != nil {
+			return nil, fmt.Errorf("Unexpected %s line: %s", file, line)
+		}
+		values[fields[0]] = value
+	}
+	return values, nil
+}
+
+// Parses the `key=value` fields of a line in the nested keyed files, skipping the
+// non-numeric ones
+func parseNestedKeys(line string) map[string]uint64 {
+	values := make(map[string]uint64)
+	for _, field := range strings.Fields(line) {
+		key, value, ok := strings.Cut(field, "=")
+		if !ok {
+			continue
+		}
+		if value, err := strconv.ParseUint(value, 10, 64); err == nil {
+			values[key] = value
+		}
+	}
+	return values
 }
 
 func readMemoryPeak(cgroupPath string) (uint64, error) {
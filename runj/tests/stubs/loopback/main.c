@@ -0,0 +1,38 @@
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+int main(void) {
+    struct sockaddr_in addr;
+    socklen_t len = sizeof(addr);
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+    int server = socket(AF_INET, SOCK_STREAM, 0);
+    if (server < 0 || bind(server, (struct sockaddr *)&addr, len) < 0 || listen(server, 1) < 0 ||
+        getsockname(server, (struct sockaddr *)&addr, &len) < 0) {
+        perror("server");
+        return 1;
+    }
+
+    int client = socket(AF_INET, SOCK_STREAM, 0);
+    if (client < 0 || connect(client, (struct sockaddr *)&addr, len) < 0) {
+        perror("client");
+        return 2;
+    }
+
+    int conn = accept(server, NULL, NULL);
+    char buffer[5] = {0};
+    if (conn < 0 || write(client, "ping", 4) != 4 || read(conn, buffer, 4) != 4 ||
+        strcmp(buffer, "ping") != 0) {
+        perror("transfer");
+        return 3;
+    }
+
+    printf("%s\n", buffer);
+    return 0;
+}
@@ -0,0 +1,21 @@
+import { fileURLToPath } from "node:url";
+import { resolve } from "node:path";
+import assert from "node:assert";
+
+export default {
+  config: {
+    cwd: "/",
+    command: ["main"],
+    mounts: [
+      {
+        from: `${resolve(fileURLToPath(import.meta.url), "../main")}`,
+        to: "/usr/local/bin/main",
+        options: ["exec"],
+      },
+    ],
+    network: "loopback",
+  },
+  check: (report) => {
+    assert.strictEqual(report.status, "NORMAL");
+  },
+};